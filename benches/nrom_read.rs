@@ -0,0 +1,51 @@
+//! Sweeps $8000-$FFFF of a single-bank NROM cartridge, comparing the precomputed mirror
+//! view `Nrom::cpu_read` uses with computing the 16KB mirror on every read.
+//!
+//! Run with `cargo bench --bench nrom_read`.
+
+use nerust_jg::cartridge::RomBuilder;
+use nerust_jg::mapper::{Mapper, Nrom};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SWEEPS: u32 = 2_000;
+
+// How NROM reads worked before the mirror was precomputed
+fn mirrored_read(prg_rom: &[u8], addr: u16) -> u8 {
+    let mut addr = addr - 0x8000;
+    if prg_rom.len() == 0x4000 && addr >= 0x4000 {
+        addr %= 0x4000;
+    }
+    prg_rom[addr as usize]
+}
+
+fn time_sweeps(mut read: impl FnMut(u16) -> u8) -> Duration {
+    let start = Instant::now();
+    let mut sum = 0u32;
+    for _ in 0..SWEEPS {
+        for addr in 0x8000..=0xFFFFu16 {
+            sum = sum.wrapping_add(read(black_box(addr)) as u32);
+        }
+    }
+    black_box(sum);
+    start.elapsed()
+}
+
+fn main() {
+    let prg_rom: Vec<u8> = (0..0x4000).map(|i| (i * 7 + i / 256) as u8).collect();
+    let rom = RomBuilder::new().prg(prg_rom.clone()).build();
+    let nrom = Nrom::new(&rom);
+
+    let reads = SWEEPS as f64 * 0x8000 as f64;
+    let precomputed = time_sweeps(|addr| nrom.cpu_read(addr));
+    let per_read = time_sweeps(|addr| mirrored_read(&prg_rom, addr));
+
+    for (name, elapsed) in [("precomputed", precomputed), ("per-read mirror", per_read)] {
+        println!(
+            "{:<16} {:>8.2?}  {:.2} ns/read",
+            name,
+            elapsed,
+            elapsed.as_nanos() as f64 / reads
+        );
+    }
+}
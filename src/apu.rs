@@ -0,0 +1,337 @@
+use crate::region::Region;
+
+/// Output rate of the console's audio
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// The two pulse channels differ only in how their sweep units negate
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum PulseChannel {
+    One,
+    Two,
+}
+
+/// Sweep unit ($4001/$4005), periodically bends the pulse timer period up or down
+#[derive(Clone)]
+pub struct Sweep {
+    channel: PulseChannel,
+    enabled: bool,
+    period: u8,
+    negate: bool,
+    shift: u8,
+    divider: u8,
+    reload: bool,
+}
+
+impl Sweep {
+    pub fn new(channel: PulseChannel) -> Self {
+        Sweep {
+            channel,
+            enabled: false,
+            period: 0,
+            negate: false,
+            shift: 0,
+            divider: 0,
+            reload: false,
+        }
+    }
+
+    // EPPP NSSS: enable, divider period, negate, shift count
+    pub fn write(&mut self, data: u8) {
+        self.enabled = data & 0b1000_0000 != 0;
+        self.period = (data >> 4) & 0b111;
+        self.negate = data & 0b1000 != 0;
+        self.shift = data & 0b111;
+        self.reload = true;
+    }
+
+    /// Period the sweep would move the timer to. Pulse 1 negates with one's complement,
+    /// so a downward sweep lands one lower than on pulse 2.
+    pub fn target_period(&self, timer_period: u16) -> u16 {
+        let change = timer_period >> self.shift;
+        match (self.negate, self.channel) {
+            (false, _) => timer_period + change,
+            (true, PulseChannel::One) => timer_period.saturating_sub(change + 1),
+            (true, PulseChannel::Two) => timer_period - change,
+        }
+    }
+
+    /// The channel is silenced while the period is too low or the target overflows 11 bits,
+    /// even if the sweep itself is disabled
+    pub fn is_muting(&self, timer_period: u16) -> bool {
+        timer_period < 8 || self.target_period(timer_period) > 0x7FF
+    }
+
+    /// Half-frame clock from the frame counter
+    pub fn clock(&mut self, timer_period: &mut u16) {
+        if self.divider == 0 && self.enabled && self.shift != 0 && !self.is_muting(*timer_period) {
+            *timer_period = self.target_period(*timer_period);
+        }
+
+        if self.divider == 0 || self.reload {
+            self.divider = self.period;
+            self.reload = false;
+        } else {
+            self.divider -= 1;
+        }
+    }
+}
+
+// Frame counter steps in CPU cycles (4-step mode), quarter frames at each, half frames at 2 and 4
+const FRAME_QUARTERS: [u32; 4] = [7457, 14913, 22371, 29829];
+const FRAME_LENGTH: u32 = 29830;
+
+#[rustfmt::skip]
+const LENGTH_TABLE: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14,
+    12, 16, 24, 18, 48, 20, 96, 22, 192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+const DUTY_TABLE: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+
+/// Volume envelope, either a constant volume or a decay from 15 clocked every quarter frame
+#[derive(Clone)]
+struct Envelope {
+    start: bool,
+    looping: bool,
+    constant_volume: bool,
+    volume: u8,
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    fn new() -> Self {
+        Envelope {
+            start: false,
+            looping: false,
+            constant_volume: false,
+            volume: 0,
+            divider: 0,
+            decay: 0,
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = self.volume;
+        } else if self.divider == 0 {
+            self.divider = self.volume;
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if self.looping {
+                self.decay = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        match self.constant_volume {
+            true => self.volume,
+            false => self.decay,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Pulse {
+    pub timer_period: u16,
+    pub sweep: Sweep,
+    enabled: bool,
+    duty: u8,
+    envelope: Envelope,
+    length_counter: u8,
+    timer: u16,
+    sequence_step: usize,
+}
+
+impl Pulse {
+    pub fn new(channel: PulseChannel) -> Self {
+        Pulse {
+            timer_period: 0,
+            sweep: Sweep::new(channel),
+            enabled: false,
+            duty: 0,
+            envelope: Envelope::new(),
+            length_counter: 0,
+            timer: 0,
+            sequence_step: 0,
+        }
+    }
+
+    // DDLC VVVV: duty, length counter halt / envelope loop, constant volume, volume
+    pub fn write_control(&mut self, data: u8) {
+        self.duty = data >> 6;
+        self.envelope.looping = data & 0b0010_0000 != 0;
+        self.envelope.constant_volume = data & 0b0001_0000 != 0;
+        self.envelope.volume = data & 0b1111;
+    }
+
+    pub fn write_sweep(&mut self, data: u8) {
+        self.sweep.write(data);
+    }
+
+    pub fn write_timer_low(&mut self, data: u8) {
+        self.timer_period = (self.timer_period & 0x0700) | data as u16;
+    }
+
+    // LLLL LTTT: length counter load, timer high bits; also restarts the envelope and sequencer
+    pub fn write_timer_high(&mut self, data: u8) {
+        self.timer_period = (self.timer_period & 0x00FF) | ((data as u16 & 0b111) << 8);
+        if self.enabled {
+            self.length_counter = LENGTH_TABLE[(data >> 3) as usize];
+        }
+        self.sequence_step = 0;
+        self.envelope.start = true;
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.length_counter = 0;
+        }
+    }
+
+    pub fn clock_sweep(&mut self) {
+        self.sweep.clock(&mut self.timer_period);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.sweep.is_muting(self.timer_period)
+    }
+
+    // Clocked every APU cycle (every other CPU cycle)
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.timer_period;
+            self.sequence_step = (self.sequence_step + 1) % 8;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn clock_quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+
+    fn clock_half_frame(&mut self) {
+        if self.length_counter > 0 && !self.envelope.looping {
+            self.length_counter -= 1;
+        }
+        self.clock_sweep();
+    }
+
+    /// Current 4-bit output level
+    pub fn output(&self) -> u8 {
+        if self.length_counter == 0
+            || self.is_muted()
+            || DUTY_TABLE[self.duty as usize][self.sequence_step] == 0
+        {
+            return 0;
+        }
+        self.envelope.output()
+    }
+}
+
+/// Audio processing unit, currently the two pulse channels behind a 4-step frame counter
+#[derive(Clone)]
+pub struct Apu {
+    pub pulse1: Pulse,
+    pub pulse2: Pulse,
+    cycle: u64,
+    frame_cycle: u32,
+    cycles_per_sample: f64,
+    sample_clock: f64,
+}
+
+impl Apu {
+    /// Produces samples at `sample_rate` Hz from the NTSC CPU clock
+    pub fn new(sample_rate: u32) -> Self {
+        Apu {
+            pulse1: Pulse::new(PulseChannel::One),
+            pulse2: Pulse::new(PulseChannel::Two),
+            cycle: 0,
+            frame_cycle: 0,
+            cycles_per_sample: Region::Ntsc.cpu_clock_hz() / sample_rate as f64,
+            sample_clock: 0.0,
+        }
+    }
+
+    /// CPU write to $4000-$4007 or $4015, other addresses are ignored
+    pub fn write_register(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x4000 => self.pulse1.write_control(data),
+            0x4001 => self.pulse1.write_sweep(data),
+            0x4002 => self.pulse1.write_timer_low(data),
+            0x4003 => self.pulse1.write_timer_high(data),
+            0x4004 => self.pulse2.write_control(data),
+            0x4005 => self.pulse2.write_sweep(data),
+            0x4006 => self.pulse2.write_timer_low(data),
+            0x4007 => self.pulse2.write_timer_high(data),
+            0x4015 => {
+                self.pulse1.set_enabled(data & 0b01 != 0);
+                self.pulse2.set_enabled(data & 0b10 != 0);
+            }
+            _ => {}
+        }
+    }
+
+    /// Advances one CPU cycle
+    pub fn tick(&mut self) {
+        if self.cycle % 2 == 1 {
+            self.pulse1.clock_timer();
+            self.pulse2.clock_timer();
+        }
+        self.cycle += 1;
+
+        self.frame_cycle += 1;
+        if let Some(step) = FRAME_QUARTERS.iter().position(|&c| c == self.frame_cycle) {
+            self.pulse1.clock_quarter_frame();
+            self.pulse2.clock_quarter_frame();
+            if step % 2 == 1 {
+                self.pulse1.clock_half_frame();
+                self.pulse2.clock_half_frame();
+            }
+        }
+        if self.frame_cycle == FRAME_LENGTH {
+            self.frame_cycle = 0;
+        }
+    }
+
+    /// Mixed output in 0.0-1.0 using the non-linear pulse mixer approximation
+    pub fn output(&self) -> f32 {
+        let pulse = (self.pulse1.output() + self.pulse2.output()) as f32;
+        match pulse {
+            0.0 => 0.0,
+            _ => 95.88 / (8128.0 / pulse + 100.0),
+        }
+    }
+
+    /// Runs for `cycles` CPU cycles and returns the samples produced meanwhile.
+    /// The same register writes and cycle counts always give the same samples.
+    pub fn render_samples(&mut self, cycles: u64) -> Vec<f32> {
+        let mut samples = vec![];
+        self.render_samples_into(cycles, &mut samples);
+        samples
+    }
+
+    /// Same as `render_samples`, appending to `samples` instead of allocating
+    pub fn render_samples_into(&mut self, cycles: u64, samples: &mut Vec<f32>) {
+        for _ in 0..cycles {
+            self.tick();
+            self.sample_clock += 1.0;
+            if self.sample_clock >= self.cycles_per_sample {
+                self.sample_clock -= self.cycles_per_sample;
+                samples.push(self.output());
+            }
+        }
+    }
+}
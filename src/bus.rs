@@ -1,125 +1,381 @@
-use crate::cartridge::Rom;
+use crate::apu::{Apu, SAMPLE_RATE};
+use crate::cartridge::{Mirroring, Rom, RomError};
 use crate::cpu::Memory;
-use crate::ppu::PPU;
+use crate::joypad::{Joypad, JoypadButton};
+use crate::mapper::{self, Mapper};
+use crate::ppu::{OAM_SIZE, PPU};
+use crate::region::Region;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 
 const RAM: u16 = 0x0000;
 const RAM_MIRRORS_END: u16 = 0x1fff;
 const PPU_REGISTERS: u16 = 0x2000;
 const PPU_REGISTERS_MIRRORS_END: u16 = 0x3fff;
+const IO_REGISTERS: u16 = 0x4000;
+const IO_REGISTERS_END: u16 = 0x401f;
+// Devices can only be mapped over the I/O registers and the expansion area, so RAM, PPU
+// and cartridge accesses never pay for the device lookup
+const IO_DEVICES: u16 = 0x4000;
+const IO_DEVICES_END: u16 = 0x7fff;
+
+// Controller ports only drive the low bits; the rest is open bus, still holding the
+// $40 high byte of the address
+const CONTROLLER_OPEN_BUS: u8 = 0x40;
+
+/// Read/write counters for each region of the CPU address space
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AccessStats {
+    pub ram_reads: u64,
+    pub ram_writes: u64,
+    pub ppu_reads: u64,
+    pub ppu_writes: u64,
+    pub io_reads: u64,
+    pub io_writes: u64,
+    pub cartridge_reads: u64,
+    pub cartridge_writes: u64,
+}
+
+impl AccessStats {
+    fn record_read(&mut self, addr: u16) {
+        match addr {
+            RAM..=RAM_MIRRORS_END => self.ram_reads += 1,
+            PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => self.ppu_reads += 1,
+            IO_REGISTERS..=IO_REGISTERS_END => self.io_reads += 1,
+            _ => self.cartridge_reads += 1,
+        }
+    }
+
+    fn record_write(&mut self, addr: u16) {
+        match addr {
+            RAM..=RAM_MIRRORS_END => self.ram_writes += 1,
+            PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => self.ppu_writes += 1,
+            IO_REGISTERS..=IO_REGISTERS_END => self.io_writes += 1,
+            _ => self.cartridge_writes += 1,
+        }
+    }
+}
+
+/// Hardware answering at a CPU address, e.g. expansion ports or debug registers
+pub trait IoDevice {
+    fn read(&mut self, addr: u16) -> u8;
+
+    fn write(&mut self, addr: u16, data: u8);
+}
+
+/// RAM, PPU, APU, controllers and cartridge state of a `Bus`, for save states. Mapped
+/// IO devices and access counters are left out.
+#[derive(Clone)]
+pub struct BusState {
+    cpu_vram: [u8; 2048],
+    mapper: Vec<u8>,
+    ppu: PPU,
+    joypad1: Joypad,
+    joypad2: Joypad,
+    apu: Apu,
+    irq_line: bool,
+    ppu_open_bus: u8,
+    dma_started: bool,
+    region: Region,
+    cpu_cycles: u64,
+}
 
 pub struct Bus {
     cpu_vram: [u8; 2048],
-    rom: Rom,
+    mapper: Rc<RefCell<dyn Mapper>>,
     ppu: PPU,
+    pub joypad1: Joypad,
+    pub joypad2: Joypad,
+    pub apu: Apu,
+    // APU output since the last `take_samples`, rendered as the CPU ticks the bus
+    samples: Vec<f32>,
+    io_devices: HashMap<u16, Box<dyn IoDevice>>,
+    stats: Option<AccessStats>,
+    irq_line: bool,
+    // Last value on the PPU's data bus, what reads of its write-only registers return
+    ppu_open_bus: u8,
+    dma_started: bool,
+    region: Region,
+    // CPU cycles ticked so far, so PAL's fractional dots per cycle add up
+    cpu_cycles: u64,
 }
 
 impl Bus {
-    pub fn new(rom: Rom) -> Self {
-        let ppu = PPU::new(rom.chr_rom.clone(), rom.screen_mirroring);
-        Bus {
+    /// Fails when the ROM's mapper has no board, e.g. for a hand-built `Rom`
+    pub fn new(rom: Rom) -> Result<Self, RomError> {
+        let mapper = mapper::new_mapper(&rom)?;
+        let ppu = PPU::new(mapper.clone());
+        Ok(Bus {
             cpu_vram: [0; 2048],
-            rom,
+            mapper,
             ppu,
-        }
+            joypad1: Joypad::new(),
+            joypad2: Joypad::new(),
+            apu: Apu::new(SAMPLE_RATE),
+            samples: Vec::new(),
+            io_devices: HashMap::new(),
+            stats: None,
+            irq_line: false,
+            ppu_open_bus: 0,
+            dma_started: false,
+            region: Region::default(),
+            cpu_cycles: 0,
+        })
     }
 
-    fn read_prg_rom(&self, mut addr: u16) -> u8 {
-        addr -= 0x8000;
-        if self.rom.prg_rom.len() == 0x4000 && addr >= 0x4000 {
-            addr = addr % 0x4000;
-        }
-        self.rom.prg_rom[addr as usize]
+    pub fn ppu(&self) -> &PPU {
+        &self.ppu
     }
 
-    fn mem_read(&mut self, addr: u16) -> u8 {
-        match addr {
-            RAM..=RAM_MIRRORS_END => {
-                let mirror_down_addr = addr & 0b00000111_11111111;
-                self.cpu_vram[mirror_down_addr as usize]
-            }
-            0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 | 0x4014 => {
-                panic!("Attempt to read from write-only PPU address {:x}", addr);
-            }
-            0x2007 => self.ppu.read_data(),
+    pub fn region(&self) -> Region {
+        self.region
+    }
 
-            0x2008..=PPU_REGISTERS_MIRRORS_END => {
-                let mirror_down_addr = addr & 0b00100000_00000111;
-                self.mem_read(mirror_down_addr)
-            }
-            0x8000..=0xFFFF => self.read_prg_rom(addr),
+    /// Sets the TV system, which decides how many PPU dots each CPU cycle runs
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+    }
 
-            _ => {
-                println!("Ignoring mem access at {}", addr);
-                0
-            }
-        }
+    /// Forces the PPU's nametable mirroring, for ROMs with a wrong header
+    pub fn override_mirroring(&mut self, mirroring: Mirroring) {
+        self.ppu.override_mirroring(Some(mirroring));
     }
 
-    fn mem_write(&mut self, addr: u16, data: u8) {
+    /// Presses or releases `button` on controller 1, seen by the game on its next strobe
+    pub fn set_joypad1_button(&mut self, button: JoypadButton, pressed: bool) {
+        self.joypad1.set_button_pressed_status(button, pressed);
+    }
+
+    /// Routes CPU reads and writes of `addr` to `device`, ahead of the built-in handling.
+    /// Panics unless `addr` is in $4000-$7FFF.
+    pub fn map_io(&mut self, addr: u16, device: Box<dyn IoDevice>) {
+        assert!(
+            (IO_DEVICES..=IO_DEVICES_END).contains(&addr),
+            "IO devices map into $4000-$7FFF, not {:04x}",
+            addr
+        );
+        self.io_devices.insert(addr, device);
+    }
+
+    fn io_device(&mut self, addr: u16) -> Option<&mut Box<dyn IoDevice>> {
         match addr {
-            RAM..=RAM_MIRRORS_END => {
-                let mirror_down_addr = addr & 0b11111111111;
-                self.cpu_vram[mirror_down_addr as usize] = data;
-            }
-            0x2000 => {
-                self.ppu.write_to_control_reg(data);
-            }
+            IO_DEVICES..=IO_DEVICES_END => self.io_devices.get_mut(&addr),
+            _ => None,
+        }
+    }
 
-            0x2006 => {
-                self.ppu.write_to_addr_reg(data);
-            }
-            0x2007 => {
-                self.ppu.write_to_data_reg(data);
-            }
+    /// Drives the IRQ line for devices outside the bus, such as the APU frame counter.
+    /// The cartridge's mapper is checked on its own.
+    pub fn set_irq_line(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
 
-            0x2008..=PPU_REGISTERS_MIRRORS_END => {
-                let mirror_down_addr = addr & 0b00100000_00000111;
-                self.mem_write(mirror_down_addr, data);
-            }
-            0x8000..=0xFFFF => panic!("Attempt to write to Cartridge ROM space: {:x}", addr),
+    /// Starts counting memory accesses per region, resetting any previous counts
+    pub fn enable_access_stats(&mut self) {
+        self.stats = Some(AccessStats::default());
+    }
 
-            _ => {
-                println!("Ignoring mem write-access at {}", addr);
-            }
+    pub fn disable_access_stats(&mut self) {
+        self.stats = None;
+    }
+
+    /// Returns the access counts, or `None` when counting is disabled
+    pub fn access_stats(&self) -> Option<AccessStats> {
+        self.stats
+    }
+
+    /// Hands over the audio samples produced since the last call. The APU runs with
+    /// the PPU in `tick`, so a register write only changes the samples after it.
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.samples)
+    }
+
+    pub fn save_state(&self) -> BusState {
+        BusState {
+            cpu_vram: self.cpu_vram,
+            mapper: self.mapper.borrow().snapshot(),
+            ppu: self.ppu.clone(),
+            joypad1: self.joypad1.clone(),
+            joypad2: self.joypad2.clone(),
+            apu: self.apu.clone(),
+            irq_line: self.irq_line,
+            ppu_open_bus: self.ppu_open_bus,
+            dma_started: self.dma_started,
+            region: self.region,
+            cpu_cycles: self.cpu_cycles,
         }
     }
+
+    /// Puts back a `save_state` taken from a bus with the same cartridge
+    pub fn load_state(&mut self, state: &BusState) {
+        self.cpu_vram = state.cpu_vram;
+        self.mapper.borrow_mut().restore(&state.mapper);
+        self.ppu = state.ppu.clone();
+        self.ppu.mapper = self.mapper.clone();
+        self.joypad1 = state.joypad1.clone();
+        self.joypad2 = state.joypad2.clone();
+        self.apu = state.apu.clone();
+        self.irq_line = state.irq_line;
+        self.ppu_open_bus = state.ppu_open_bus;
+        self.dma_started = state.dma_started;
+        self.region = state.region;
+        self.cpu_cycles = state.cpu_cycles;
+    }
 }
 
 impl Memory for Bus {
-    fn mem_read(&self, addr: u16) -> u8 {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        if let Some(stats) = self.stats.as_mut() {
+            stats.record_read(addr);
+        }
+
+        if let Some(device) = self.io_device(addr) {
+            return device.read(addr);
+        }
+
         match addr {
             RAM..=RAM_MIRRORS_END => {
                 let mirror_down_addr = addr & 0b00000111_11111111;
                 self.cpu_vram[mirror_down_addr as usize]
             }
             PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => {
-                let _mirror_down_addr = addr & 0b00100000_00000111;
-                todo!("PPU_REGISTERS not implemented");
-            }
-            0x8000..=0xFFFF => self.read_prg_rom(addr),
-            _ => {
-                println!("Ignoring memory address at {}", addr);
-                0
+                let mirror_down_addr = addr & 0b00100000_00000111;
+                let value = match mirror_down_addr {
+                    0x2002 => self.ppu.read_status(),
+                    0x2004 => self.ppu.read_oam_data(),
+                    0x2007 => self.ppu.read_data(),
+                    _ => self.ppu_open_bus,
+                };
+                self.ppu_open_bus = value;
+                value
             }
+            0x4016 => CONTROLLER_OPEN_BUS | self.joypad1.read() | self.joypad2.microphone_bit(),
+            0x4017 => CONTROLLER_OPEN_BUS | self.joypad2.read(),
+            0x8000..=0xFFFF => self.mapper.borrow().cpu_read(addr),
+            // Nothing else is mapped, reads come back as zero
+            _ => 0,
         }
     }
 
     fn mem_write(&mut self, addr: u16, value: u8) {
+        if let Some(stats) = self.stats.as_mut() {
+            stats.record_write(addr);
+        }
+
+        if let Some(device) = self.io_device(addr) {
+            device.write(addr, value);
+            return;
+        }
+
         match addr {
             RAM..=RAM_MIRRORS_END => {
                 let mirror_down_addr = addr & 0b00000111_11111111;
                 self.cpu_vram[mirror_down_addr as usize] = value;
             }
             PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => {
-                let _mirror_down_addr = addr & 0b00100000_00000111;
-                todo!("PPU_REGISTERS not implemented");
+                let mirror_down_addr = addr & 0b00100000_00000111;
+                self.ppu_open_bus = value;
+                match mirror_down_addr {
+                    0x2000 => self.ppu.write_to_control_reg(value),
+                    0x2001 => self.ppu.write_to_mask(value),
+                    0x2003 => self.ppu.write_to_oam_addr(value),
+                    0x2004 => self.ppu.write_to_oam_data(value),
+                    0x2005 => self.ppu.write_to_scroll(value),
+                    0x2006 => self.ppu.write_to_addr_reg(value),
+                    0x2007 => self.ppu.write_to_data_reg(value),
+                    _ => {} // $2002 is read-only
+                }
             }
-            0x8000..=0xFFFF => {
-                panic!("Cannot write to ROM");
+            0x4014 => {
+                let page = (value as u16) << 8;
+                let mut data = [0; OAM_SIZE];
+                for (i, byte) in data.iter_mut().enumerate() {
+                    *byte = self.mem_read(page + i as u16);
+                }
+                self.ppu.oam_dma(&data);
+                self.dma_started = true;
             }
-            _ => {
-                println!("Ignoring memory write-address at {}", addr);
+            0x4000..=0x4013 | 0x4015 | 0x4017 => self.apu.write_register(addr, value),
+            0x4016 => {
+                self.joypad1.write(value);
+                self.joypad2.write(value);
             }
+            0x8000..=0xFFFF => {
+                let mut mapper = self.mapper.borrow_mut();
+                let value = match mapper.has_bus_conflicts() {
+                    true => value & mapper.cpu_read(addr),
+                    false => value,
+                };
+                mapper.cpu_write(addr, value);
+            }
+            _ => {}
+        }
+    }
+
+    // Mapped devices can't be read without side effects, so they show as open bus
+    fn peek(&self, addr: u16) -> u8 {
+        // Device reads may have side effects, so report the last value on the bus instead
+        if (IO_DEVICES..=IO_DEVICES_END).contains(&addr) && self.io_devices.contains_key(&addr) {
+            return self.ppu_open_bus;
+        }
+
+        match addr {
+            RAM..=RAM_MIRRORS_END => self.cpu_vram[(addr & 0b00000111_11111111) as usize],
+            PPU_REGISTERS..=PPU_REGISTERS_MIRRORS_END => match addr & 0b00100000_00000111 {
+                0x2002 => self.ppu.peek_status(),
+                0x2004 => self.ppu.read_oam_data(),
+                0x2007 => self.ppu.peek_data(),
+                _ => self.ppu_open_bus,
+            },
+            0x4016 => CONTROLLER_OPEN_BUS | self.joypad1.peek() | self.joypad2.microphone_bit(),
+            0x4017 => CONTROLLER_OPEN_BUS | self.joypad2.peek(),
+            0x8000..=0xFFFF => self.mapper.borrow().cpu_read(addr),
+            _ => 0,
+        }
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        let before = self.region.cpu_to_ppu_cycles(self.cpu_cycles);
+        self.cpu_cycles += cycles as u64;
+        let dots = self.region.cpu_to_ppu_cycles(self.cpu_cycles) - before;
+        self.ppu.tick(dots as u32);
+        self.apu
+            .render_samples_into(cycles as u64, &mut self.samples);
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        self.ppu.poll_nmi()
+    }
+
+    fn irq_line(&self) -> bool {
+        self.irq_line || self.mapper.borrow().irq_pending()
+    }
+
+    fn poll_dma(&mut self) -> bool {
+        std::mem::take(&mut self.dma_started)
+    }
+
+    // Runs inside RAM are copied in one go, split where the 2KB mirror repeats. Other
+    // addresses, or every address while counting accesses, go through `mem_write`.
+    fn mem_write_slice(&mut self, start: u16, data: &[u8]) {
+        let mut addr = start;
+        let mut rest = data;
+        while !rest.is_empty() {
+            let len = match addr {
+                RAM..=RAM_MIRRORS_END if self.stats.is_none() => {
+                    let index = (addr & 0b00000111_11111111) as usize;
+                    let len = rest.len().min(self.cpu_vram.len() - index);
+                    self.cpu_vram[index..index + len].copy_from_slice(&rest[..len]);
+                    len
+                }
+                _ => {
+                    self.mem_write(addr, rest[0]);
+                    1
+                }
+            };
+            addr = addr.wrapping_add(len as u16);
+            rest = &rest[len..];
         }
     }
 }
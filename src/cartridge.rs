@@ -1,4 +1,11 @@
+use crate::mapper;
+use std::fmt;
+
 const NES_TAG: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
+const FDS_TAG: [u8; 4] = [0x46, 0x44, 0x53, 0x1A];
+const UNIF_TAG: [u8; 4] = [0x55, 0x4E, 0x49, 0x46];
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
 
 pub const PRG_ROM_PAGE_SIZE: usize = 16384;
 pub const CHR_ROM_PAGE_SIZE: usize = 8192;
@@ -10,6 +17,66 @@ pub enum Mirroring {
     FourScreen,
 }
 
+/// Non-iNES image formats that are recognised but can't be loaded
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Format {
+    Fds,
+    Unif,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RomError {
+    InvalidFormat,
+    UnsupportedFormat(Format),
+    UnsupportedMapper(u8),
+    UnsupportedVersion,
+    EmptyPrgRom,
+    Truncated { expected: usize, actual: usize },
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RomError::InvalidFormat => write!(f, "File is not an iNES file format"),
+            RomError::UnsupportedFormat(Format::Fds) => {
+                write!(f, "Famicom Disk System images are not supported")
+            }
+            RomError::UnsupportedFormat(Format::Unif) => write!(f, "UNIF files are not supported"),
+            RomError::UnsupportedMapper(mapper) => write!(f, "Mapper {} is not supported", mapper),
+            RomError::UnsupportedVersion => write!(f, "NES2.0 format is not supported"),
+            RomError::EmptyPrgRom => write!(f, "Header declares no PRG ROM"),
+            RomError::Truncated { expected, actual } => write!(
+                f,
+                "File is truncated: expected {} bytes, found {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RomError {}
+
+/// Header metadata of an iNES file, read without copying PRG/CHR data
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RomInfo {
+    pub mapper: u8,
+    pub prg_rom_size: usize,
+    pub chr_rom_size: usize,
+    pub screen_mirroring: Mirroring,
+    pub has_trainer: bool,
+    pub has_battery: bool,
+}
+
+impl RomInfo {
+    fn prg_rom_start(&self) -> usize {
+        HEADER_SIZE + if self.has_trainer { TRAINER_SIZE } else { 0 }
+    }
+
+    fn chr_rom_start(&self) -> usize {
+        self.prg_rom_start() + self.prg_rom_size
+    }
+}
+
 pub struct Rom {
     pub prg_rom: Vec<u8>,
     pub chr_rom: Vec<u8>,
@@ -18,9 +85,38 @@ pub struct Rom {
 }
 
 impl Rom {
-    pub fn new(raw: &Vec<u8>) -> Result<Rom, String> {
-        if &raw[0..4] != NES_TAG {
-            return Err("File is not an iNES file format".to_string());
+    pub fn new(raw: &[u8]) -> Result<Rom, String> {
+        let info = Rom::validate(raw).map_err(|e| e.to_string())?;
+        if !mapper::is_supported(info.mapper) {
+            return Err(RomError::UnsupportedMapper(info.mapper).to_string());
+        }
+
+        let prg_rom_start = info.prg_rom_start();
+        let chr_rom_start = info.chr_rom_start();
+
+        Ok(Rom {
+            prg_rom: raw[prg_rom_start..prg_rom_start + info.prg_rom_size].to_vec(),
+            chr_rom: raw[chr_rom_start..chr_rom_start + info.chr_rom_size].to_vec(),
+            mapper: info.mapper,
+            screen_mirroring: info.screen_mirroring,
+        })
+    }
+
+    /// Replaces the mirroring read from the header, for dumps that have it wrong
+    pub fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.screen_mirroring = mirroring;
+    }
+
+    /// Checks that `raw` is a loadable iNES file and returns its header metadata
+    pub fn validate(raw: &[u8]) -> Result<RomInfo, RomError> {
+        match raw.get(0..4) {
+            Some(tag) if tag == FDS_TAG => return Err(RomError::UnsupportedFormat(Format::Fds)),
+            Some(tag) if tag == UNIF_TAG => return Err(RomError::UnsupportedFormat(Format::Unif)),
+            _ => {}
+        }
+
+        if raw.len() < HEADER_SIZE || raw[0..4] != NES_TAG {
+            return Err(RomError::InvalidFormat);
         }
 
         let mapper = (raw[7] & 0b1111_0000) | (raw[6] >> 4);
@@ -28,7 +124,12 @@ impl Rom {
         let ines_ver = (raw[7] >> 2) & 0b11;
 
         if ines_ver != 0 {
-            return Err("NES2.0 format is not supported".to_string());
+            return Err(RomError::UnsupportedVersion);
+        }
+
+        // Every mapper picks banks modulo the PRG size, so there must be at least one page
+        if raw[4] == 0 {
+            return Err(RomError::EmptyPrgRom);
         }
 
         let four_screen = raw[6] & 0b1000 != 0;
@@ -41,20 +142,120 @@ impl Rom {
             },
         };
 
-        let prg_rom_size = (raw[4] as u16) as usize * PRG_ROM_PAGE_SIZE;
-        let chr_rom_size = (raw[5] as u16) as usize * CHR_ROM_PAGE_SIZE;
+        let info = RomInfo {
+            mapper,
+            prg_rom_size: raw[4] as usize * PRG_ROM_PAGE_SIZE,
+            chr_rom_size: raw[5] as usize * CHR_ROM_PAGE_SIZE,
+            screen_mirroring,
+            has_trainer: raw[6] & 0b100 != 0,
+            has_battery: raw[6] & 0b10 != 0,
+        };
 
-        let skip_trainer = raw[6] & 0b100 != 0;
+        let expected = info.chr_rom_start() + info.chr_rom_size;
+        if raw.len() < expected {
+            return Err(RomError::Truncated {
+                expected,
+                actual: raw.len(),
+            });
+        }
 
-        let prg_rom_start = 16 + if skip_trainer { 512 } else { 0 };
-        let chr_rom_start = prg_rom_start + prg_rom_size;
+        Ok(info)
+    }
+}
 
-        Ok(Rom {
-            prg_rom: raw[prg_rom_start..prg_rom_start + prg_rom_size].to_vec(),
-            chr_rom: raw[chr_rom_start..chr_rom_start + chr_rom_size].to_vec(),
-            mapper: mapper,
-            screen_mirroring: screen_mirroring,
-        })
+/// Writes `addr` little-endian at `offset`, the byte order of the 6502 vectors
+pub fn write_vector(buf: &mut [u8], offset: usize, addr: u16) {
+    buf[offset..offset + 2].copy_from_slice(&addr.to_le_bytes());
+}
+
+/// Assembles an iNES image for tests and tools
+pub struct RomBuilder {
+    prg_rom: Vec<u8>,
+    chr_rom: Vec<u8>,
+    mapper: u8,
+    screen_mirroring: Mirroring,
+    reset_vector: Option<u16>,
+}
+
+impl Default for RomBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RomBuilder {
+    /// One empty 16KB PRG bank, no CHR, NROM with horizontal mirroring
+    pub fn new() -> Self {
+        RomBuilder {
+            prg_rom: vec![],
+            chr_rom: vec![],
+            mapper: 0,
+            screen_mirroring: Mirroring::Horizontal,
+            reset_vector: None,
+        }
+    }
+
+    /// PRG ROM contents, zero-padded up to a whole number of 16KB banks
+    pub fn prg(mut self, prg_rom: Vec<u8>) -> Self {
+        self.prg_rom = prg_rom;
+        self
+    }
+
+    /// CHR ROM contents, zero-padded up to a whole number of 8KB banks
+    pub fn chr(mut self, chr_rom: Vec<u8>) -> Self {
+        self.chr_rom = chr_rom;
+        self
+    }
+
+    pub fn mapper(mut self, mapper: u8) -> Self {
+        self.mapper = mapper;
+        self
+    }
+
+    pub fn mirroring(mut self, screen_mirroring: Mirroring) -> Self {
+        self.screen_mirroring = screen_mirroring;
+        self
+    }
+
+    /// Stored at the end of the last PRG bank, which NROM and UxROM map at $FFFC
+    pub fn reset_vector(mut self, addr: u16) -> Self {
+        self.reset_vector = Some(addr);
+        self
+    }
+
+    /// Raw iNES bytes: header, PRG banks, CHR banks
+    pub fn to_ines(&self) -> Vec<u8> {
+        let prg_banks = self.prg_rom.len().div_ceil(PRG_ROM_PAGE_SIZE).max(1);
+        let chr_banks = self.chr_rom.len().div_ceil(CHR_ROM_PAGE_SIZE);
+
+        let mut prg_rom = self.prg_rom.clone();
+        prg_rom.resize(prg_banks * PRG_ROM_PAGE_SIZE, 0);
+        if let Some(addr) = self.reset_vector {
+            let offset = prg_rom.len() - 4;
+            write_vector(&mut prg_rom, offset, addr);
+        }
+        let mut chr_rom = self.chr_rom.clone();
+        chr_rom.resize(chr_banks * CHR_ROM_PAGE_SIZE, 0);
+
+        let mirroring = match self.screen_mirroring {
+            Mirroring::Horizontal => 0b0000,
+            Mirroring::Vertical => 0b0001,
+            Mirroring::FourScreen => 0b1000,
+        };
+
+        let mut raw = NES_TAG.to_vec();
+        raw.push(prg_banks as u8);
+        raw.push(chr_banks as u8);
+        raw.push((self.mapper << 4) | mirroring);
+        raw.push(self.mapper & 0xF0);
+        raw.resize(HEADER_SIZE, 0);
+        raw.extend(prg_rom);
+        raw.extend(chr_rom);
+        raw
+    }
+
+    pub fn build(&self) -> Rom {
+        Rom::new(&self.to_ines()).unwrap()
     }
 }
 
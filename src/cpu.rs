@@ -1,7 +1,10 @@
 use crate::bus::Bus;
-use crate::opcodes;
+use crate::disasm;
+use crate::opcodes::{self, OpCode};
 use bitflags::bitflags;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::ops::{ControlFlow, Range};
 
 bitflags! {
 
@@ -18,17 +21,123 @@ bitflags! {
     }
 }
 
+impl CpuFlags {
+    /// Flags as `NV-BDIZC`, with a `.` for each clear bit
+    pub fn to_display_string(&self) -> String {
+        "NV-BDIZC"
+            .chars()
+            .enumerate()
+            .map(|(i, name)| match self.bits() & (0x80 >> i) {
+                0 => '.',
+                _ => name,
+            })
+            .collect()
+    }
+}
+
 pub const STACK: u16 = 0x0100;
 const STACK_RESET: u8 = 0xfd;
 
-pub struct CPU {
+/// Unofficial KIL/JAM opcode, used by test programs to stop `run` without an interrupt
+pub const HALT_OPCODE: u8 = 0x02;
+
+const NMI_VECTOR: u16 = 0xfffa;
+const RESET_VECTOR: u16 = 0xfffc;
+const IRQ_VECTOR: u16 = 0xfffe;
+
+// Zero bytes at the PC, i.e. back-to-back BRKs, taken as the PC having run off into
+// uninitialised memory
+const ZERO_REGION_TRAP_BYTES: u16 = 8;
+
+// XAA and LAX #imm OR A with a chip- and temperature-dependent constant before the AND,
+// $EE is the value most often measured
+const UNSTABLE_MAGIC: u8 = 0xee;
+
+/// Why `run` stopped other than KIL
+#[derive(Debug, PartialEq, Clone)]
+pub enum CpuError {
+    IllegalOpcode { addr: u16, code: u8 },
+    UnimplementedOpcode { addr: u16, code: u8 },
+    ZeroRegion { addr: u16 },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::IllegalOpcode { addr, code } => write!(
+                f,
+                "Unofficial opcode {:02x} at {:04x} with illegal opcodes disabled",
+                code, addr
+            ),
+            CpuError::UnimplementedOpcode { addr, code } => {
+                write!(
+                    f,
+                    "Opcode {:02x} at {:04x} is not yet implemented",
+                    code, addr
+                )
+            }
+            CpuError::ZeroRegion { addr } => {
+                write!(f, "Program counter ran into zeroed memory at {:04x}", addr)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CpuError {}
+
+pub struct CPU<M: Memory = Bus> {
     pub register_a: u8,
     pub register_x: u8,
     pub register_y: u8,
     pub status: CpuFlags,
     pub program_counter: u16,
     pub stack_pointer: u8,
-    pub bus: Bus,
+    pub halted: bool,
+    /// CPU cycles elapsed, using the base cycle count of each instruction
+    pub cycles: u64,
+    pub bus: M,
+    nmi_line: bool,
+    nmi_pending: bool,
+    irq_line: bool,
+    trace_history: Option<TraceHistory>,
+    trace_filter: Option<Range<u16>>,
+    illegal_opcodes: bool,
+    zero_region_trap: bool,
+    decimal_mode: bool,
+    error: Option<CpuError>,
+}
+
+/// Registers and interrupt lines of a `CPU`, for save states. Debugging settings such as
+/// the trace history are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuState {
+    register_a: u8,
+    register_x: u8,
+    register_y: u8,
+    status: CpuFlags,
+    program_counter: u16,
+    stack_pointer: u8,
+    halted: bool,
+    cycles: u64,
+    nmi_line: bool,
+    nmi_pending: bool,
+    irq_line: bool,
+    error: Option<CpuError>,
+}
+
+/// The last `capacity` executed instructions as (pc, opcode), oldest first
+struct TraceHistory {
+    capacity: usize,
+    entries: VecDeque<(u16, u8)>,
+}
+
+impl TraceHistory {
+    fn record(&mut self, pc: u16, code: u8) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((pc, code));
+    }
 }
 
 #[derive(Debug)]
@@ -43,15 +152,22 @@ pub enum AddressingMode {
     Absolute_Y,
     Indirect_X,
     Indirect_Y,
-    NoneAddressing,
+    Indirect,
+    Implied,
+    Accumulator,
+    Relative,
 }
 
 pub trait Memory {
-    fn mem_read(&self, addr: u16) -> u8;
+    fn mem_read(&mut self, addr: u16) -> u8;
 
     fn mem_write(&mut self, addr: u16, value: u8);
 
-    fn mem_read_u16(&self, pos: u16) -> u16 {
+    /// Reads `addr` without side effects, for debuggers. Registers whose reads change
+    /// state report what a read would return without changing it.
+    fn peek(&self, addr: u16) -> u8;
+
+    fn mem_read_u16(&mut self, pos: u16) -> u16 {
         let low = self.mem_read(pos);
         let high = self.mem_read(pos + 1);
         (high as u16) << 8 | (low as u16)
@@ -63,10 +179,148 @@ pub trait Memory {
         self.mem_write(pos, low);
         self.mem_write(pos + 1, high);
     }
+
+    /// Advances whatever is clocked alongside the CPU by `cycles` CPU cycles
+    fn tick(&mut self, _cycles: u32) {}
+
+    /// Takes an NMI raised since the last poll, e.g. by the PPU entering vblank
+    fn poll_nmi(&mut self) -> bool {
+        false
+    }
+
+    /// Whether a device, e.g. a mapper or the APU, is holding the IRQ line
+    fn irq_line(&self) -> bool {
+        false
+    }
+
+    /// Takes an OAM DMA started since the last poll, which halts the CPU while it copies
+    fn poll_dma(&mut self) -> bool {
+        false
+    }
+
+    /// Writes `data` from `start` up, wrapping from $FFFF to $0000
+    fn mem_write_slice(&mut self, start: u16, data: &[u8]) {
+        for (i, &value) in data.iter().enumerate() {
+            self.mem_write(start.wrapping_add(i as u16), value);
+        }
+    }
+}
+
+/// Plain 64KB of RAM with nothing mapped, for exercising the CPU without a cartridge
+pub struct FlatMemory(pub [u8; 0x10000]);
+
+impl Default for FlatMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatMemory {
+    pub fn new() -> Self {
+        FlatMemory([0; 0x10000])
+    }
+}
+
+impl Memory for FlatMemory {
+    fn mem_read(&mut self, addr: u16) -> u8 {
+        self.0[addr as usize]
+    }
+
+    fn mem_write(&mut self, addr: u16, value: u8) {
+        self.0[addr as usize] = value;
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.0[addr as usize]
+    }
+}
+
+// Where an operand lives, and the address indexing started from. The two only differ
+// for the indexed modes, where a different high byte means the index crossed a page.
+struct OperandAddress {
+    base: u16,
+    addr: u16,
+}
+
+impl OperandAddress {
+    fn page_crossed(&self) -> bool {
+        self.base & 0xFF00 != self.addr & 0xFF00
+    }
+}
+
+// The addressing math shared by execution, cycle counting and tracing; `read` decides
+// whether pointer fetches are real bus reads or peeks
+fn resolve_operand(
+    mode: &AddressingMode,
+    pc: u16,
+    operand: [u8; 2],
+    x: u8,
+    y: u8,
+    mut read: impl FnMut(u16) -> u8,
+) -> OperandAddress {
+    let zero_page = operand[0];
+    let absolute = u16::from_le_bytes(operand);
+    let mut read_zero_page_u16 = |ptr: u8| {
+        let lo = read(ptr as u16);
+        let hi = read(ptr.wrapping_add(1) as u16);
+        (hi as u16) << 8 | (lo as u16)
+    };
+    let unindexed = |addr| OperandAddress { base: addr, addr };
+
+    match mode {
+        AddressingMode::Immediate => unindexed(pc),
+        AddressingMode::ZeroPage => unindexed(zero_page as u16),
+        AddressingMode::Absolute => unindexed(absolute),
+        AddressingMode::ZeroPage_X => unindexed(zero_page.wrapping_add(x) as u16),
+        AddressingMode::ZeroPage_Y => unindexed(zero_page.wrapping_add(y) as u16),
+        AddressingMode::Absolute_X => OperandAddress {
+            base: absolute,
+            addr: absolute.wrapping_add(x as u16),
+        },
+        AddressingMode::Absolute_Y => OperandAddress {
+            base: absolute,
+            addr: absolute.wrapping_add(y as u16),
+        },
+        AddressingMode::Indirect_X => unindexed(read_zero_page_u16(zero_page.wrapping_add(x))),
+        AddressingMode::Indirect_Y => {
+            let base = read_zero_page_u16(zero_page);
+            OperandAddress {
+                base,
+                addr: base.wrapping_add(y as u16),
+            }
+        }
+        // Signed offset from the address of the next instruction
+        AddressingMode::Relative => {
+            unindexed(pc.wrapping_add(1).wrapping_add((zero_page as i8) as u16))
+        }
+        AddressingMode::Indirect => {
+            // The high byte is fetched without carrying into the pointer's page
+            let lo = read(absolute);
+            let hi = read((absolute & 0xFF00) | (absolute.wrapping_add(1) & 0x00FF));
+            unindexed((hi as u16) << 8 | (lo as u16))
+        }
+        AddressingMode::Implied | AddressingMode::Accumulator => {
+            panic!("mode {:?} is not supported", mode);
+        }
+    }
 }
 
-impl Memory for CPU {
-    fn mem_read(&self, addr: u16) -> u8 {
+/// Operand address for `mode` computed from explicit register values, the same math as
+/// `CPU::get_operand_address`. `pc` is the address of the operand and `operand` the bytes
+/// stored there; `mem` is only peeked at for the indirect modes.
+pub fn effective_address(
+    mode: &AddressingMode,
+    pc: u16,
+    operand: [u8; 2],
+    x: u8,
+    y: u8,
+    mem: &impl Memory,
+) -> u16 {
+    resolve_operand(mode, pc, operand, x, y, |addr| mem.peek(addr)).addr
+}
+
+impl<M: Memory> Memory for CPU<M> {
+    fn mem_read(&mut self, addr: u16) -> u8 {
         self.bus.mem_read(addr)
     }
 
@@ -74,75 +328,124 @@ impl Memory for CPU {
         self.bus.mem_write(addr, value);
     }
 
-    fn mem_read_u16(&self, pos: u16) -> u16 {
+    fn peek(&self, addr: u16) -> u8 {
+        self.bus.peek(addr)
+    }
+
+    fn mem_read_u16(&mut self, pos: u16) -> u16 {
         self.bus.mem_read_u16(pos)
     }
 
     fn mem_write_u16(&mut self, pos: u16, value: u16) {
         self.bus.mem_write_u16(pos, value);
     }
+
+    fn tick(&mut self, cycles: u32) {
+        self.bus.tick(cycles);
+    }
+
+    fn poll_nmi(&mut self) -> bool {
+        self.bus.poll_nmi()
+    }
+
+    fn irq_line(&self) -> bool {
+        self.bus.irq_line()
+    }
+
+    fn poll_dma(&mut self) -> bool {
+        self.bus.poll_dma()
+    }
 }
 
-impl CPU {
-    pub fn new(bus: Bus) -> Self {
+impl<M: Memory> CPU<M> {
+    pub fn new(bus: M) -> Self {
         CPU {
             register_a: 0,
             register_x: 0,
             register_y: 0,
             stack_pointer: STACK_RESET,
             program_counter: 0,
-            status: CpuFlags::from_bits_truncate(0b100100),
-            bus: bus,
+            status: CpuFlags::INTERRUPT_DISABLE | CpuFlags::BREAK2,
+            halted: false,
+            cycles: 0,
+            bus,
+            nmi_line: false,
+            nmi_pending: false,
+            irq_line: false,
+            trace_history: None,
+            trace_filter: None,
+            illegal_opcodes: true,
+            zero_region_trap: false,
+            decimal_mode: false,
+            error: None,
         }
     }
 
-    pub fn get_operand_address(&self, mode: &AddressingMode) -> u16 {
-        match mode {
-            AddressingMode::Immediate => self.program_counter,
-
-            AddressingMode::ZeroPage => self.mem_read(self.program_counter) as u16,
-
-            AddressingMode::Absolute => self.mem_read_u16(self.program_counter),
+    /// Only the operand bytes the mode uses are fetched, each through a real bus read
+    pub fn get_operand_address(&mut self, mode: &AddressingMode) -> u16 {
+        self.operand_address(mode).addr
+    }
 
-            AddressingMode::ZeroPage_X => {
-                let pos = self.mem_read(self.program_counter);
-                pos.wrapping_add(self.register_x) as u16
-            }
-            AddressingMode::ZeroPage_Y => {
-                let pos = self.mem_read(self.program_counter);
-                pos.wrapping_add(self.register_y) as u16
-            }
+    fn operand_address(&mut self, mode: &AddressingMode) -> OperandAddress {
+        let pc = self.program_counter;
+        let len = match mode {
+            AddressingMode::Immediate | AddressingMode::Implied | AddressingMode::Accumulator => 0,
+            AddressingMode::Absolute
+            | AddressingMode::Absolute_X
+            | AddressingMode::Absolute_Y
+            | AddressingMode::Indirect => 2,
+            _ => 1,
+        };
+        let mut operand = [0; 2];
+        for (i, byte) in operand.iter_mut().take(len).enumerate() {
+            *byte = self.mem_read(pc.wrapping_add(i as u16));
+        }
+        let (x, y) = (self.register_x, self.register_y);
+        resolve_operand(mode, pc, operand, x, y, |addr| self.bus.mem_read(addr))
+    }
 
-            AddressingMode::Absolute_X => {
-                let base = self.mem_read_u16(self.program_counter);
-                base.wrapping_add(self.register_x as u16)
-            }
-            AddressingMode::Absolute_Y => {
-                let base = self.mem_read_u16(self.program_counter);
-                base.wrapping_add(self.register_y as u16)
+    /// Whether indexing the operand's base address carries into the high byte. Memory is
+    /// only peeked at, the instruction itself does the real reads.
+    fn page_crossed(&self, mode: &AddressingMode) -> bool {
+        match mode {
+            AddressingMode::Absolute_X
+            | AddressingMode::Absolute_Y
+            | AddressingMode::Indirect_Y => {
+                let pc = self.program_counter;
+                let operand = [self.bus.peek(pc), self.bus.peek(pc.wrapping_add(1))];
+                let (x, y) = (self.register_x, self.register_y);
+                resolve_operand(mode, pc, operand, x, y, |addr| self.bus.peek(addr)).page_crossed()
             }
+            _ => false,
+        }
+    }
 
-            AddressingMode::Indirect_X => {
-                let base = self.mem_read(self.program_counter);
-
-                let ptr: u8 = base.wrapping_add(self.register_x);
-                let lo = self.mem_read(ptr as u16);
-                let hi = self.mem_read(ptr.wrapping_add(1) as u16);
-                (hi as u16) << 8 | (lo as u16)
-            }
-            AddressingMode::Indirect_Y => {
-                let base = self.mem_read(self.program_counter);
+    // SHY, SHX, AHX and TAS store `value & (H + 1)`, H being the high byte of the base
+    // address. When indexing crosses a page the stored value also replaces the high byte
+    // of the address written to.
+    fn unstable_store(&mut self, mode: &AddressingMode, value: u8) {
+        let operand = self.operand_address(mode);
+        let value = value & ((operand.base >> 8) as u8).wrapping_add(1);
+        let addr = match operand.page_crossed() {
+            true => (value as u16) << 8 | (operand.addr & 0x00FF),
+            false => operand.addr,
+        };
+        self.mem_write(addr, value);
+    }
 
-                let lo = self.mem_read(base as u16);
-                let hi = self.mem_read(base.wrapping_add(1) as u16);
-                let deref_base = (hi as u16) << 8 | (lo as u16);
-                deref_base.wrapping_add(self.register_y as u16)
-            }
+    // *SAX stores A AND X without touching the flags
+    fn sax(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        self.mem_write(addr, self.register_a & self.register_x);
+    }
 
-            AddressingMode::NoneAddressing => {
-                panic!("mode {:?} is not supported", mode);
-            }
+    // The unofficial NOPs still read their operand
+    fn nop_read(&mut self, mode: &AddressingMode) {
+        if let AddressingMode::Immediate = mode {
+            return;
         }
+        let addr = self.get_operand_address(mode);
+        self.mem_read(addr);
     }
 
     fn ldy(&mut self, mode: &AddressingMode) {
@@ -233,24 +536,305 @@ impl CPU {
         self.update_zero_and_negative_flags(self.register_y);
     }
 
+    /// Drives the NMI input, an interrupt is latched on the low-to-high edge
+    pub fn set_nmi_line(&mut self, asserted: bool) {
+        if asserted && !self.nmi_line {
+            self.nmi_pending = true;
+        }
+        self.nmi_line = asserted;
+    }
+
+    /// Drives the IRQ input, serviced between instructions for as long as it stays asserted
+    pub fn set_irq_line(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
+
+    /// Whether unofficial opcodes execute (the default) or halt the CPU with
+    /// `CpuError::IllegalOpcode`
+    pub fn set_illegal_opcodes(&mut self, enabled: bool) {
+        self.illegal_opcodes = enabled;
+    }
+
+    /// Whether ADC and SBC honour the D flag with BCD arithmetic like a stock 6502. Off by
+    /// default since the 2A03 has the BCD unit cut out, but some CPU test ROMs expect it
+    pub fn set_decimal_mode(&mut self, enabled: bool) {
+        self.decimal_mode = enabled;
+    }
+
+    /// When enabled, a BRK followed by more zero bytes halts the CPU with
+    /// `CpuError::ZeroRegion` instead of running, to catch a PC that ran off into
+    /// uninitialised memory. The bytes ahead of the PC are only peeked at, so the check
+    /// doesn't disturb I/O registers or access statistics.
+    pub fn trap_on_zero_region(&mut self, enabled: bool) {
+        self.zero_region_trap = enabled;
+    }
+
+    fn in_zero_region(&self, addr: u16) -> bool {
+        (0..ZERO_REGION_TRAP_BYTES).all(|offset| self.bus.peek(addr.wrapping_add(offset)) == 0)
+    }
+
+    /// The whole 64KB CPU address space as `peek` sees it, for memory viewers
+    pub fn snapshot_address_space(&self) -> Vec<u8> {
+        (0..=0xFFFF).map(|addr| self.bus.peek(addr)).collect()
+    }
+
+    /// Address of the instruction after the one at the PC, where a debugger stepping over
+    /// a JSR puts its temporary breakpoint. Unknown opcodes count as one byte.
+    pub fn next_instruction_addr(&self) -> u16 {
+        let code = self.bus.peek(self.program_counter);
+        let len = opcodes::OPCODES_MAP.get(&code).map_or(1, |op| op.len);
+        self.program_counter.wrapping_add(len as u16)
+    }
+
+    /// CPU cycles run since power-on, the same count as `cycles`
+    pub fn total_cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// What halted the CPU, if it was an error rather than KIL
+    pub fn error(&self) -> Option<&CpuError> {
+        self.error.as_ref()
+    }
+
+    pub fn save_state(&self) -> CpuState {
+        CpuState {
+            register_a: self.register_a,
+            register_x: self.register_x,
+            register_y: self.register_y,
+            status: self.status,
+            program_counter: self.program_counter,
+            stack_pointer: self.stack_pointer,
+            halted: self.halted,
+            cycles: self.cycles,
+            nmi_line: self.nmi_line,
+            nmi_pending: self.nmi_pending,
+            irq_line: self.irq_line,
+            error: self.error.clone(),
+        }
+    }
+
+    /// Puts the registers back as `save_state` found them, the bus is restored separately
+    pub fn load_state(&mut self, state: &CpuState) {
+        self.register_a = state.register_a;
+        self.register_x = state.register_x;
+        self.register_y = state.register_y;
+        self.status = state.status;
+        self.program_counter = state.program_counter;
+        self.stack_pointer = state.stack_pointer;
+        self.halted = state.halted;
+        self.cycles = state.cycles;
+        self.nmi_line = state.nmi_line;
+        self.nmi_pending = state.nmi_pending;
+        self.irq_line = state.irq_line;
+        self.error = state.error.clone();
+    }
+
+    /// Starts recording the last `capacity` executed instructions, dropping any previous history
+    pub fn enable_trace_history(&mut self, capacity: usize) {
+        self.trace_history = Some(TraceHistory {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        });
+    }
+
+    pub fn disable_trace_history(&mut self) {
+        self.trace_history = None;
+    }
+
+    /// Only traces instructions whose address is in `range`: the trace history,
+    /// `run_with_callback` and `filtered_trace` skip everything outside it
+    pub fn set_trace_filter(&mut self, range: Range<u16>) {
+        self.trace_filter = Some(range);
+    }
+
+    pub fn clear_trace_filter(&mut self) {
+        self.trace_filter = None;
+    }
+
+    // Whether the instruction at the program counter passes the trace filter
+    fn in_trace_filter(&self) -> bool {
+        match &self.trace_filter {
+            Some(range) => range.contains(&self.program_counter),
+            None => true,
+        }
+    }
+
+    /// Recorded (pc, opcode) pairs, oldest first, empty when recording is disabled
+    pub fn trace_history(&self) -> Vec<(u16, u8)> {
+        match &self.trace_history {
+            Some(history) => history.entries.iter().copied().collect(),
+            None => vec![],
+        }
+    }
+
+    // Appended to panic messages so bug reports show how the CPU got there
+    fn trace_history_report(&self) -> String {
+        self.trace_history()
+            .iter()
+            .map(|(pc, code)| format!("\n  {:04X}: {:02X}", pc, code))
+            .collect()
+    }
+
+    /// nestest-style line for the instruction at the program counter, e.g.
+    /// `0064  11 33     ORA ($33),Y = 0400 @ 0400 = AA  A:00 X:00 Y:00 P:24 SP:FD`.
+    /// Memory is only peeked at, so tracing doesn't change what the program reads.
+    pub fn trace(&self) -> String {
+        let begin = self.program_counter;
+        let code = self.bus.peek(begin);
+        let opcode = opcodes::OPCODES_MAP.get(&code).copied();
+        let bytes: Vec<u8> = (0..opcode.map_or(1, |op| op.len) as u16)
+            .map(|i| self.bus.peek(begin.wrapping_add(i)))
+            .collect();
+
+        let mut asm_str = disasm::disassemble_instruction(&bytes, begin);
+        if let Some(opcode) = opcode {
+            asm_str += &self.trace_access(opcode, begin, &bytes);
+        }
+
+        format!(
+            "{:47} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+            asm_str,
+            self.register_a,
+            self.register_x,
+            self.register_y,
+            self.status.bits(),
+            self.stack_pointer
+        )
+    }
+
+    /// `trace`, or `None` when the program counter is outside the trace filter
+    pub fn filtered_trace(&self) -> Option<String> {
+        self.in_trace_filter().then(|| self.trace())
+    }
+
+    // Where the operand points and what is stored there, in nestest's notation
+    fn trace_access(&self, opcode: &OpCode, begin: u16, bytes: &[u8]) -> String {
+        let operand = [
+            bytes.get(1).copied().unwrap_or(0),
+            bytes.get(2).copied().unwrap_or(0),
+        ];
+        let (x, y) = (self.register_x, self.register_y);
+        let addr = match (&opcode.mode, opcode.code) {
+            (AddressingMode::Immediate, _)
+            | (AddressingMode::Implied, _)
+            | (AddressingMode::Accumulator, _)
+            | (AddressingMode::Relative, _)
+            | (_, 0x20 | 0x4c) => return String::new(),
+            (mode, _) => effective_address(mode, begin.wrapping_add(1), operand, x, y, &self.bus),
+        };
+
+        // Jump pointers are only followed, never read as data
+        if let AddressingMode::Indirect = opcode.mode {
+            return format!(" = {:04X}", addr);
+        }
+        let value = self.bus.peek(addr);
+        match opcode.mode {
+            AddressingMode::ZeroPage_X | AddressingMode::ZeroPage_Y => {
+                format!(" @ {:02X} = {:02X}", addr, value)
+            }
+            AddressingMode::Absolute_X | AddressingMode::Absolute_Y => {
+                format!(" @ {:04X} = {:02X}", addr, value)
+            }
+            AddressingMode::Indirect_X => format!(
+                " @ {:02X} = {:04X} = {:02X}",
+                operand[0].wrapping_add(x),
+                addr,
+                value
+            ),
+            AddressingMode::Indirect_Y => format!(
+                " = {:04X} @ {:04X} = {:02X}",
+                addr.wrapping_sub(y as u16),
+                addr,
+                value
+            ),
+            _ => format!(" = {:02X}", value),
+        }
+    }
+
+    pub fn reset_vector(&mut self) -> u16 {
+        self.mem_read_u16(RESET_VECTOR)
+    }
+
+    pub fn nmi_vector(&mut self) -> u16 {
+        self.mem_read_u16(NMI_VECTOR)
+    }
+
+    /// Shared by IRQ and BRK
+    pub fn irq_vector(&mut self) -> u16 {
+        self.mem_read_u16(IRQ_VECTOR)
+    }
+
+    // 7 cycles: two dummy reads of the next opcode, three pushes, two vector reads
+    fn interrupt(&mut self, vector: u16) {
+        self.mem_read(self.program_counter);
+        self.mem_read(self.program_counter);
+        self.stack_push_u16(self.program_counter);
+        let mut flags = self.status;
+        flags.remove(CpuFlags::BREAK);
+        flags.insert(CpuFlags::BREAK2);
+        self.stack_push(flags.bits());
+        self.status.insert(CpuFlags::INTERRUPT_DISABLE);
+
+        self.program_counter = vector;
+        self.cycles += 7;
+        self.bus.tick(7);
+    }
+
+    fn interrupt_nmi(&mut self) {
+        self.nmi_pending = false;
+        let vector = self.nmi_vector();
+        self.interrupt(vector);
+    }
+
+    fn interrupt_irq(&mut self) {
+        let vector = self.irq_vector();
+        self.interrupt(vector);
+    }
+
+    // An IRQ raised by software: the pushed return address skips the padding byte after
+    // the opcode and the pushed status has B set, so the handler can tell the two apart
+    fn brk(&mut self) {
+        self.stack_push_u16(self.program_counter.wrapping_add(1));
+        let mut flags = self.status;
+        flags.insert(CpuFlags::BREAK);
+        flags.insert(CpuFlags::BREAK2);
+        self.stack_push(flags.bits());
+        self.status.insert(CpuFlags::INTERRUPT_DISABLE);
+
+        self.program_counter = self.irq_vector();
+    }
+
+    fn poll_interrupts(&mut self) {
+        if self.bus.poll_nmi() {
+            self.nmi_pending = true;
+        }
+        if self.nmi_pending {
+            self.interrupt_nmi();
+        } else if (self.irq_line || self.bus.irq_line())
+            && !self.status.contains(CpuFlags::INTERRUPT_DISABLE)
+        {
+            self.interrupt_irq();
+        }
+    }
+
     pub fn load_and_run(&mut self, program: Vec<u8>) {
         self.load(program);
-        self.program_counter = self.mem_read_u16(0xFFFC);
+        self.program_counter = self.reset_vector();
         self.run();
     }
 
     pub fn load(&mut self, program: Vec<u8>) {
         for i in 0..(program.len() as u16) {
-            self.mem_write(0x0000 + i, program[i as usize]);
+            self.mem_write(i, program[i as usize]);
         }
-        self.mem_write_u16(0xFFFC, 0x0000);
+        self.mem_write_u16(RESET_VECTOR, 0x0000);
     }
 
     pub fn load_at(&mut self, program: Vec<u8>, start_addr: u16) {
         for (i, byte) in program.iter().enumerate() {
             self.mem_write(start_addr + i as u16, *byte);
         }
-        self.mem_write_u16(0xFFFC, start_addr);
+        self.mem_write_u16(RESET_VECTOR, start_addr);
     }
 
     pub fn reset(&mut self) {
@@ -258,9 +842,12 @@ impl CPU {
         self.register_x = 0;
         self.register_y = 0;
         self.stack_pointer = STACK_RESET;
-        self.status = CpuFlags::from_bits_truncate(0b100100);
+        self.status = CpuFlags::INTERRUPT_DISABLE | CpuFlags::BREAK2;
+        self.halted = false;
+        self.error = None;
+        self.nmi_pending = false;
 
-        self.program_counter = self.mem_read_u16(0xFFFC);
+        self.program_counter = self.reset_vector();
     }
 
     fn set_carry_flag(&mut self) {
@@ -271,7 +858,12 @@ impl CPU {
         self.status.remove(CpuFlags::CARRY);
     }
 
-    //* NOTE: Ignoring decimal mode
+    // BCD only applies when enabled with `set_decimal_mode` and the D flag is set
+    fn decimal_active(&self) -> bool {
+        self.decimal_mode && self.status.contains(CpuFlags::DECIMAL_MODE)
+    }
+
+    // The 2A03 has no BCD unit: SED/CLD still change the D flag, but the sum is always binary
     fn add_to_register_a(&mut self, value: u8) {
         let sum = self.register_a as u16
             + value as u16
@@ -291,7 +883,8 @@ impl CPU {
 
         let result = sum as u8;
 
-        if (value ^ result) & (value ^ result) & 0x80 != 0 {
+        // Signed overflow: both inputs share a sign that the result doesn't have
+        if (value ^ result) & (self.register_a ^ result) & 0x80 != 0 {
             self.status.insert(CpuFlags::OVERFLOW);
         } else {
             self.status.remove(CpuFlags::OVERFLOW);
@@ -303,30 +896,96 @@ impl CPU {
     fn sbc(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
         let value = self.mem_read(addr);
-        self.add_to_register_a(((value as i8).wrapping_neg().wrapping_sub(1)) as u8);
+        self.subtract_with_borrow(value);
+    }
+
+    fn subtract_with_borrow(&mut self, value: u8) {
+        if self.decimal_active() {
+            self.subtract_decimal(value);
+            return;
+        }
+        // A - M - (1 - C) == A + !M + C, so the carry out is the inverted borrow
+        self.add_to_register_a(!value);
     }
 
     fn adc(&mut self, mode: &AddressingMode) {
         let addr = self.get_operand_address(mode);
         let value = self.mem_read(addr);
-        self.add_to_register_a(value);
+        self.add_with_carry(value);
+    }
+
+    fn add_with_carry(&mut self, value: u8) {
+        match self.decimal_active() {
+            true => self.add_decimal(value),
+            false => self.add_to_register_a(value),
+        }
+    }
+
+    // NMOS BCD add: each nibble over 9 is corrected by 6 and carries into the next.
+    // V comes from the corrected low nibble plus the uncorrected high one, as on the 6502
+    fn add_decimal(&mut self, value: u8) {
+        let a = self.register_a;
+        let carry_in = self.status.contains(CpuFlags::CARRY) as u8;
+
+        let mut lo = (a & 0x0F) + (value & 0x0F) + carry_in;
+        if lo > 9 {
+            lo = (lo + 6) & 0x0F | 0x10;
+        }
+        let mut sum = (a & 0xF0) as u16 + (value & 0xF0) as u16 + lo as u16;
+
+        let partial = sum as u8;
+        self.status.set(
+            CpuFlags::OVERFLOW,
+            (value ^ partial) & (a ^ partial) & 0x80 != 0,
+        );
+
+        if sum > 0x9F {
+            sum += 0x60;
+        }
+        self.status.set(CpuFlags::CARRY, sum > 0xFF);
+        self.set_register_a(sum as u8);
+    }
+
+    // NMOS BCD subtract: the carry and V are those of the binary subtraction, each nibble
+    // that borrowed is corrected by 6
+    fn subtract_decimal(&mut self, value: u8) {
+        let a = self.register_a;
+        let borrow = !self.status.contains(CpuFlags::CARRY) as i16;
+
+        let binary = a as i16 - value as i16 - borrow;
+        let result = binary as u8;
+        self.status
+            .set(CpuFlags::OVERFLOW, (a ^ value) & (a ^ result) & 0x80 != 0);
+        self.status.set(CpuFlags::CARRY, binary >= 0);
+
+        let mut lo = (a & 0x0F) as i16 - (value & 0x0F) as i16 - borrow;
+        let mut hi = (a >> 4) as i16 - (value >> 4) as i16;
+        if lo < 0 {
+            lo -= 6;
+            hi -= 1;
+        }
+        if hi < 0 {
+            hi -= 6;
+        }
+        self.set_register_a(((hi << 4) | (lo & 0x0F)) as u8);
     }
 
     fn stack_pop(&mut self) -> u8 {
         self.stack_pointer = self.stack_pointer.wrapping_add(1);
-        self.mem_read((STACK as u16) + self.stack_pointer as u16)
+        self.mem_read(STACK + self.stack_pointer as u16)
     }
 
     fn stack_push(&mut self, value: u8) {
-        self.mem_write((STACK as u16) + self.stack_pointer as u16, value);
+        self.mem_write(STACK + self.stack_pointer as u16, value);
         self.stack_pointer = self.stack_pointer.wrapping_sub(1);
     }
 
+    // High byte first, so the value sits little-endian in memory
     fn stack_push_u16(&mut self, value: u16) {
-        let low = (value >> 8) as u8;
-        let high = (value & 0xFF) as u8;
-        self.stack_push(low);
+        let high = (value >> 8) as u8;
+        let low = (value & 0xFF) as u8;
         self.stack_push(high);
+        self.stack_push(low);
     }
 
     fn stack_pop_u16(&mut self) -> u16 {
@@ -335,6 +994,14 @@ impl CPU {
         (high << 8) | low
     }
 
+    // Read-modify-writes store the unmodified value back while the ALU works on it, a
+    // cycle before the result. Mappers that count writes see both.
+    fn read_for_modify(&mut self, addr: u16) -> u8 {
+        let value = self.mem_read(addr);
+        self.mem_write(addr, value);
+        value
+    }
+
     fn asl_accumulator(&mut self) {
         let mut value = self.register_a;
         if value >> 7 == 1 {
@@ -342,19 +1009,19 @@ impl CPU {
         } else {
             self.clear_carry_flag();
         }
-        value = value << 1;
+        value <<= 1;
         self.set_register_a(value);
     }
 
     fn asl(&mut self, mode: &AddressingMode) -> u8 {
         let addr = self.get_operand_address(mode);
-        let mut value = self.mem_read(addr);
+        let mut value = self.read_for_modify(addr);
         if value >> 7 == 1 {
             self.set_carry_flag();
         } else {
             self.clear_carry_flag();
         }
-        value = value << 1;
+        value <<= 1;
         self.mem_write(addr, value);
         self.update_zero_and_negative_flags(value);
         value
@@ -367,19 +1034,19 @@ impl CPU {
         } else {
             self.clear_carry_flag();
         }
-        value = value >> 1;
+        value >>= 1;
         self.set_register_a(value);
     }
 
     fn lsr(&mut self, mode: &AddressingMode) -> u8 {
         let addr = self.get_operand_address(mode);
-        let mut value = self.mem_read(addr);
+        let mut value = self.read_for_modify(addr);
         if value & 1 == 1 {
             self.set_carry_flag();
         } else {
             self.clear_carry_flag();
         }
-        value = value >> 1;
+        value >>= 1;
         self.mem_write(addr, value);
         self.update_zero_and_negative_flags(value);
         value
@@ -387,7 +1054,7 @@ impl CPU {
 
     fn rol(&mut self, mode: &AddressingMode) -> u8 {
         let addr = self.get_operand_address(mode);
-        let mut value = self.mem_read(addr);
+        let mut value = self.read_for_modify(addr);
         let old_carry = self.status.contains(CpuFlags::CARRY);
 
         if value >> 7 == 1 {
@@ -395,7 +1062,7 @@ impl CPU {
         } else {
             self.clear_carry_flag();
         }
-        value = value << 1;
+        value <<= 1;
         if old_carry {
             value |= 1;
         }
@@ -413,7 +1080,7 @@ impl CPU {
         } else {
             self.clear_carry_flag();
         }
-        value = value << 1;
+        value <<= 1;
         if old_carry {
             value |= 1;
         }
@@ -422,7 +1089,7 @@ impl CPU {
 
     fn ror(&mut self, mode: &AddressingMode) -> u8 {
         let addr = self.get_operand_address(mode);
-        let mut value = self.mem_read(addr);
+        let mut value = self.read_for_modify(addr);
         let old_carry = self.status.contains(CpuFlags::CARRY);
 
         if value & 1 == 1 {
@@ -430,7 +1097,7 @@ impl CPU {
         } else {
             self.clear_carry_flag();
         }
-        value = value >> 1;
+        value >>= 1;
         if old_carry {
             value |= 0b10000000;
         }
@@ -448,7 +1115,7 @@ impl CPU {
         } else {
             self.clear_carry_flag();
         }
-        value = value >> 1;
+        value >>= 1;
         if old_carry {
             value |= 0b10000000;
         }
@@ -457,7 +1124,7 @@ impl CPU {
 
     fn inc(&mut self, mode: &AddressingMode) -> u8 {
         let addr = self.get_operand_address(mode);
-        let mut value = self.mem_read(addr);
+        let mut value = self.read_for_modify(addr);
         value = value.wrapping_add(1);
         self.mem_write(addr, value);
         self.update_zero_and_negative_flags(value);
@@ -476,7 +1143,7 @@ impl CPU {
 
     fn dec(&mut self, mode: &AddressingMode) -> u8 {
         let addr = self.get_operand_address(mode);
-        let mut value = self.mem_read(addr);
+        let mut value = self.read_for_modify(addr);
         value = value.wrapping_sub(1);
         self.mem_write(addr, value);
         self.update_zero_and_negative_flags(value);
@@ -495,7 +1162,7 @@ impl CPU {
     }
 
     fn php(&mut self) {
-        let mut flags = self.status.clone();
+        let mut flags = self.status;
         flags.insert(CpuFlags::BREAK);
         flags.insert(CpuFlags::BREAK2);
         self.stack_push(flags.bits());
@@ -517,6 +1184,10 @@ impl CPU {
     fn compare(&mut self, mode: &AddressingMode, compare_with: u8) {
         let addr = self.get_operand_address(mode);
         let value = self.mem_read(addr);
+        self.compare_value(value, compare_with);
+    }
+
+    fn compare_value(&mut self, value: u8, compare_with: u8) {
         if value <= compare_with {
             self.status.insert(CpuFlags::CARRY);
         } else {
@@ -525,15 +1196,17 @@ impl CPU {
         self.update_zero_and_negative_flags(compare_with.wrapping_sub(value));
     }
 
+    // Taken branches jump relative to the next instruction, untaken ones just skip the
+    // offset byte like any other 2-byte instruction. Taking the branch costs a cycle, and
+    // one more when the target is on another page.
     fn branch(&mut self, condition: bool) {
         if condition {
-            let jump: i8 = self.mem_read(self.program_counter) as i8;
-            let jump_addr = self
-                .program_counter
-                .wrapping_add(1)
-                .wrapping_add(jump as u16);
-
-            self.program_counter = jump_addr;
+            let next = self.program_counter.wrapping_add(1);
+            self.program_counter = self.get_operand_address(&AddressingMode::Relative);
+            self.cycles += 1;
+            if next & 0xFF00 != self.program_counter & 0xFF00 {
+                self.cycles += 1;
+            }
         }
     }
 
@@ -541,31 +1214,101 @@ impl CPU {
         self.run_with_callback(|_| {});
     }
 
+    /// Runs until KIL or an error, calling `callback` before each opcode is fetched. The
+    /// CPU it's handed still holds the registers and PC from before that instruction,
+    /// which is what trace lines are built from. Instructions outside the trace filter
+    /// are run without calling it.
     pub fn run_with_callback<F>(&mut self, mut callback: F)
     where
-        F: FnMut(&mut CPU),
+        F: FnMut(&mut CPU<M>),
+    {
+        self.run_with_mut_callback(|cpu| {
+            if cpu.in_trace_filter() {
+                callback(cpu);
+            }
+            ControlFlow::Continue(())
+        });
+    }
+
+    /// Like `run_with_callback`, but the callback can also stop the run by returning
+    /// `ControlFlow::Break`. It runs before every instruction, so state it changes
+    /// (e.g. joypad buttons on the bus) is seen by the very next instruction.
+    pub fn run_with_mut_callback<F>(&mut self, mut callback: F)
+    where
+        F: FnMut(&mut CPU<M>) -> ControlFlow<()>,
     {
-        let ref opcodes: HashMap<u8, &'static opcodes::OpCode> = *opcodes::OPCODES_MAP;
+        let opcodes: &HashMap<u8, &'static opcodes::OpCode> = &opcodes::OPCODES_MAP;
 
         loop {
-            callback(self);
+            self.poll_interrupts();
+
+            if callback(self).is_break() {
+                return;
+            }
 
+            let cycles_before = self.cycles;
             let code = self.mem_read(self.program_counter);
+            let traced = self.in_trace_filter();
+            if let Some(history) = self.trace_history.as_mut().filter(|_| traced) {
+                history.record(self.program_counter, code);
+            }
             self.program_counter += 1;
             let program_counter_state = self.program_counter;
 
-            let opcode = opcodes.get(&code).unwrap();
+            let opcode = opcodes.get(&code).unwrap_or_else(|| {
+                panic!(
+                    "Unknown opcode {:02x} at {:04x}{}",
+                    code,
+                    self.program_counter - 1,
+                    self.trace_history_report()
+                )
+            });
+            if opcode.is_unofficial() && !self.illegal_opcodes {
+                self.program_counter -= 1;
+                self.error = Some(CpuError::IllegalOpcode {
+                    addr: self.program_counter,
+                    code,
+                });
+                self.halted = true;
+                return;
+            }
+            if code == 0x00
+                && self.zero_region_trap
+                && self.in_zero_region(program_counter_state - 1)
+            {
+                self.program_counter -= 1;
+                self.error = Some(CpuError::ZeroRegion {
+                    addr: self.program_counter,
+                });
+                self.halted = true;
+                return;
+            }
+            self.cycles += opcode.cycles as u64;
+            if opcode.has_page_cross_penalty() && self.page_crossed(&opcode.mode) {
+                self.cycles += 1;
+            }
 
             match code {
                 0xa9 | 0xa5 | 0xb5 | 0xad | 0xbd | 0xb9 | 0xa1 | 0xb1 => {
                     self.lda(&opcode.mode);
                 }
+                // *LAX
+                0xa7 | 0xb7 | 0xaf | 0xbf | 0xa3 | 0xb3 => {
+                    self.lda(&opcode.mode);
+                    self.register_x = self.register_a;
+                }
                 // TAX
                 0xAA => self.tax(),
                 // INX
                 0xe8 => self.inx(),
                 // BRK
-                0x00 => return,
+                0x00 => self.brk(),
+
+                // KIL
+                HALT_OPCODE => {
+                    self.halted = true;
+                    return;
+                }
 
                 // CLD
                 0xd8 => self.status.remove(CpuFlags::DECIMAL_MODE),
@@ -610,32 +1353,36 @@ impl CPU {
                     self.ora(&opcode.mode);
                 }
                 // LSR
-                0x4a => self.lsr_accumulator(),
-
-                0x46 | 0x56 | 0x4e | 0x5e => {
-                    self.lsr(&opcode.mode);
-                }
+                0x4a | 0x46 | 0x56 | 0x4e | 0x5e => match opcode.mode {
+                    AddressingMode::Accumulator => self.lsr_accumulator(),
+                    _ => {
+                        self.lsr(&opcode.mode);
+                    }
+                },
 
                 // ASL
-                0x0a => self.asl_accumulator(),
-
-                0x06 | 0x16 | 0x0e | 0x1e => {
-                    self.asl(&opcode.mode);
-                }
+                0x0a | 0x06 | 0x16 | 0x0e | 0x1e => match opcode.mode {
+                    AddressingMode::Accumulator => self.asl_accumulator(),
+                    _ => {
+                        self.asl(&opcode.mode);
+                    }
+                },
 
                 // ROL
-                0x2a => self.rol_accumulator(),
-
-                0x26 | 0x36 | 0x2e | 0x3e => {
-                    self.rol(&opcode.mode);
-                }
+                0x2a | 0x26 | 0x36 | 0x2e | 0x3e => match opcode.mode {
+                    AddressingMode::Accumulator => self.rol_accumulator(),
+                    _ => {
+                        self.rol(&opcode.mode);
+                    }
+                },
 
                 // ROR
-                0x6a => self.ror_accumulator(),
-
-                0x66 | 0x76 | 0x6e | 0x7e => {
-                    self.ror(&opcode.mode);
-                }
+                0x6a | 0x66 | 0x76 | 0x6e | 0x7e => match opcode.mode {
+                    AddressingMode::Accumulator => self.ror_accumulator(),
+                    _ => {
+                        self.ror(&opcode.mode);
+                    }
+                },
 
                 // INC
                 0xe6 | 0xf6 | 0xee | 0xfe => {
@@ -671,31 +1418,15 @@ impl CPU {
                     self.compare(&opcode.mode, self.register_x);
                 }
 
-                // JMP Absolute
-                0x4c => {
-                    let mem_address = self.mem_read_u16(self.program_counter);
-                    self.program_counter = mem_address;
-                }
-
-                // JMP Indirect
-                0x6c => {
-                    let mem_address = self.mem_read_u16(self.program_counter);
-
-                    let indirect_ref = if mem_address & 0x00FF == 0x00FF {
-                        let low = self.mem_read(mem_address);
-                        let high = self.mem_read(mem_address & 0xFF00);
-                        (high as u16) << 8 | (low as u16)
-                    } else {
-                        self.mem_read_u16(mem_address)
-                    };
-                    self.program_counter = indirect_ref;
+                // JMP Absolute, JMP Indirect
+                0x4c | 0x6c => {
+                    self.program_counter = self.get_operand_address(&opcode.mode);
                 }
 
                 // JSR
                 0x20 => {
                     self.stack_push_u16(self.program_counter + 2 - 1);
-                    let mem_address = self.mem_read_u16(self.program_counter);
-                    self.program_counter = mem_address;
+                    self.program_counter = self.get_operand_address(&opcode.mode);
                 }
 
                 // RTS
@@ -726,6 +1457,11 @@ impl CPU {
                     self.branch(!self.status.contains(CpuFlags::NEGATIVE));
                 }
 
+                // BVS
+                0x70 => {
+                    self.branch(self.status.contains(CpuFlags::OVERFLOW));
+                }
+
                 // BMI
                 0x30 => {
                     self.branch(self.status.contains(CpuFlags::NEGATIVE));
@@ -781,6 +1517,82 @@ impl CPU {
                     // Do nothing
                 }
 
+                // Unofficial NOPs
+                0x1a | 0x3a | 0x5a | 0x7a | 0xda | 0xfa => {}
+                0x80 | 0x82 | 0x89 | 0xc2 | 0xe2 | 0x04 | 0x44 | 0x64 | 0x14 | 0x34 | 0x54
+                | 0x74 | 0xd4 | 0xf4 | 0x0c | 0x1c | 0x3c | 0x5c | 0x7c | 0xdc | 0xfc => {
+                    self.nop_read(&opcode.mode);
+                }
+
+                // Unstable unofficial opcodes, see `opcodes::UNSTABLE_OPCODES`
+                // *SHY
+                0x9c => self.unstable_store(&opcode.mode, self.register_y),
+                // *SHX
+                0x9e => self.unstable_store(&opcode.mode, self.register_x),
+                // *AHX
+                0x9f | 0x93 => self.unstable_store(&opcode.mode, self.register_a & self.register_x),
+                // *TAS
+                0x9b => {
+                    self.stack_pointer = self.register_a & self.register_x;
+                    self.unstable_store(&opcode.mode, self.stack_pointer);
+                }
+                // *LAS
+                0xbb => {
+                    let addr = self.get_operand_address(&opcode.mode);
+                    let value = self.mem_read(addr) & self.stack_pointer;
+                    self.register_x = value;
+                    self.stack_pointer = value;
+                    self.set_register_a(value);
+                }
+                // *XAA
+                0x8b => {
+                    let value = self.mem_read(self.program_counter);
+                    self.set_register_a(
+                        (self.register_a | UNSTABLE_MAGIC) & self.register_x & value,
+                    );
+                }
+                // *LAX #imm
+                0xab => {
+                    let value = self.mem_read(self.program_counter);
+                    self.register_x = (self.register_a | UNSTABLE_MAGIC) & value;
+                    self.set_register_a(self.register_x);
+                }
+
+                // Unofficial read-modify-writes: the official RMW on memory, then the
+                // accumulator op on its result
+                // *SAX
+                0x87 | 0x97 | 0x8f | 0x83 => self.sax(&opcode.mode),
+                // *SLO
+                0x03 | 0x07 | 0x0f | 0x13 | 0x17 | 0x1b | 0x1f => {
+                    let value = self.asl(&opcode.mode);
+                    self.set_register_a(self.register_a | value);
+                }
+                // *RLA
+                0x23 | 0x27 | 0x2f | 0x33 | 0x37 | 0x3b | 0x3f => {
+                    let value = self.rol(&opcode.mode);
+                    self.set_register_a(self.register_a & value);
+                }
+                // *SRE
+                0x43 | 0x47 | 0x4f | 0x53 | 0x57 | 0x5b | 0x5f => {
+                    let value = self.lsr(&opcode.mode);
+                    self.set_register_a(self.register_a ^ value);
+                }
+                // *RRA
+                0x63 | 0x67 | 0x6f | 0x73 | 0x77 | 0x7b | 0x7f => {
+                    let value = self.ror(&opcode.mode);
+                    self.add_with_carry(value);
+                }
+                // *DCP
+                0xc3 | 0xc7 | 0xcf | 0xd3 | 0xd7 | 0xdb | 0xdf => {
+                    let value = self.dec(&opcode.mode);
+                    self.compare_value(value, self.register_a);
+                }
+                // *ISB
+                0xe3 | 0xe7 | 0xef | 0xf3 | 0xf7 | 0xfb | 0xff => {
+                    let value = self.inc(&opcode.mode);
+                    self.subtract_with_borrow(value);
+                }
+
                 // TAY
                 0xa8 => {
                     self.register_y = self.register_a;
@@ -810,12 +1622,28 @@ impl CPU {
                     self.update_zero_and_negative_flags(self.register_a);
                 }
 
-                _ => todo!("Opcode {:02x} is not yet implemented", code),
+                _ => {
+                    self.program_counter = program_counter_state - 1;
+                    self.error = Some(CpuError::UnimplementedOpcode {
+                        addr: self.program_counter,
+                        code,
+                    });
+                    self.halted = true;
+                    return;
+                }
             }
 
             if program_counter_state == self.program_counter {
                 self.program_counter += (opcode.len - 1) as u16;
             }
+
+            // OAM DMA takes 513 cycles, plus one to line up with a read cycle when it
+            // starts on an odd one
+            if self.bus.poll_dma() {
+                self.cycles += 513 + self.cycles % 2;
+            }
+
+            self.bus.tick((self.cycles - cycles_before) as u32);
         }
     }
 }
@@ -0,0 +1,179 @@
+use crate::cartridge::Rom;
+use crate::cpu::AddressingMode;
+use crate::opcodes::{self, OpCode};
+use std::collections::BTreeSet;
+
+/// A decoded instruction and its raw bytes
+pub struct Instruction {
+    pub addr: u16,
+    pub opcode: &'static OpCode,
+    pub bytes: Vec<u8>,
+}
+
+impl Instruction {
+    /// Decodes the instruction at the start of `code`, or `None` for an unknown opcode
+    /// or a truncated operand
+    pub fn decode(code: &[u8], addr: u16) -> Option<Instruction> {
+        let opcode = *opcodes::OPCODES_MAP.get(code.first()?)?;
+        let bytes = code.get(..opcode.len as usize)?.to_vec();
+        Some(Instruction {
+            addr,
+            opcode,
+            bytes,
+        })
+    }
+
+    fn operand_u8(&self) -> u8 {
+        self.bytes[1]
+    }
+
+    fn operand_u16(&self) -> u16 {
+        (self.bytes[2] as u16) << 8 | (self.bytes[1] as u16)
+    }
+
+    /// Destination of a JSR, absolute JMP or relative branch
+    pub fn target(&self) -> Option<u16> {
+        match (self.opcode.code, &self.opcode.mode, self.opcode.len) {
+            (0x20 | 0x4c, _, _) => Some(self.operand_u16()),
+            (_, AddressingMode::Relative, _) => Some(
+                self.addr
+                    .wrapping_add(2)
+                    .wrapping_add((self.operand_u8() as i8) as u16),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Operand in assembler syntax, e.g. `#$05` or `($44),Y`
+    pub fn operand(&self) -> String {
+        self.format_operand(&BTreeSet::new())
+    }
+
+    fn format_operand(&self, labels: &BTreeSet<u16>) -> String {
+        if let Some(target) = self.target() {
+            return match labels.contains(&target) {
+                true => format!("L_{:04X}", target),
+                false => format!("${:04X}", target),
+            };
+        }
+
+        match (&self.opcode.mode, self.opcode.len) {
+            (AddressingMode::Immediate, _) => format!("#${:02X}", self.operand_u8()),
+            (AddressingMode::ZeroPage, _) => format!("${:02X}", self.operand_u8()),
+            (AddressingMode::ZeroPage_X, _) => format!("${:02X},X", self.operand_u8()),
+            (AddressingMode::ZeroPage_Y, _) => format!("${:02X},Y", self.operand_u8()),
+            (AddressingMode::Indirect_X, _) => format!("(${:02X},X)", self.operand_u8()),
+            (AddressingMode::Indirect_Y, _) => format!("(${:02X}),Y", self.operand_u8()),
+            (AddressingMode::Absolute, _) => format!("${:04X}", self.operand_u16()),
+            (AddressingMode::Absolute_X, _) => format!("${:04X},X", self.operand_u16()),
+            (AddressingMode::Absolute_Y, _) => format!("${:04X},Y", self.operand_u16()),
+            (AddressingMode::Accumulator, _) => String::from("A"),
+            (AddressingMode::Relative, _) => unreachable!("branches always have a target"),
+            (AddressingMode::Indirect, _) => format!("(${:04X})", self.operand_u16()),
+            (AddressingMode::Implied, _) => String::new(),
+        }
+    }
+
+    // Mnemonics are right-aligned so the unofficial `*NOP` lines up the way nestest prints it
+    fn format_line(&self, labels: &BTreeSet<u16>) -> String {
+        let hex_str = self
+            .bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<String>>()
+            .join(" ");
+        format!(
+            "{:04X}  {:8} {: >4} {}",
+            self.addr,
+            hex_str,
+            self.opcode.name,
+            self.format_operand(labels)
+        )
+        .trim_end()
+        .to_string()
+    }
+}
+
+enum Entry {
+    Code(Instruction),
+    Data(u16, u8),
+}
+
+fn decode_all(code: &[u8], origin: u16) -> Vec<Entry> {
+    let mut entries = vec![];
+    let mut offset = 0;
+    while offset < code.len() {
+        let addr = origin.wrapping_add(offset as u16);
+        match Instruction::decode(&code[offset..], addr) {
+            Some(instruction) => {
+                offset += instruction.bytes.len();
+                entries.push(Entry::Code(instruction));
+            }
+            None => {
+                entries.push(Entry::Data(addr, code[offset]));
+                offset += 1;
+            }
+        }
+    }
+    entries
+}
+
+fn listing(entries: &[Entry], labels: &BTreeSet<u16>) -> String {
+    let mut lines = vec![];
+    for entry in entries {
+        match entry {
+            Entry::Code(instruction) => {
+                if labels.contains(&instruction.addr) {
+                    lines.push(format!("L_{:04X}:", instruction.addr));
+                }
+                lines.push(instruction.format_line(labels));
+            }
+            Entry::Data(addr, byte) => {
+                lines.push(format!(
+                    "{:04X}  {:02X}        .DB ${:02X}",
+                    addr, byte, byte
+                ));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Disassembles the single instruction at the start of `code`, or a `.DB` line for a byte
+/// that doesn't decode
+pub fn disassemble_instruction(code: &[u8], addr: u16) -> String {
+    let entries = match Instruction::decode(code, addr) {
+        Some(instruction) => vec![Entry::Code(instruction)],
+        None => vec![Entry::Data(addr, code[0])],
+    };
+    listing(&entries, &BTreeSet::new())
+}
+
+/// Disassembles `code` as if it were loaded at `origin`, one instruction per line
+pub fn disassemble(code: &[u8], origin: u16) -> String {
+    listing(&decode_all(code, origin), &BTreeSet::new())
+}
+
+/// Disassembles the PRG ROM at $8000, labelling the JSR/JMP/branch targets as `L_XXXX`
+pub fn disassemble_with_labels(rom: &Rom) -> String {
+    let entries = decode_all(&rom.prg_rom, 0x8000);
+
+    let starts: BTreeSet<u16> = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Code(instruction) => Some(instruction.addr),
+            Entry::Data(..) => None,
+        })
+        .collect();
+    // Only targets that land on a decoded instruction can carry a label
+    let labels: BTreeSet<u16> = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Code(instruction) => instruction.target(),
+            Entry::Data(..) => None,
+        })
+        .filter(|addr| starts.contains(addr))
+        .collect();
+
+    listing(&entries, &labels)
+}
@@ -0,0 +1,38 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Sleeps between frames to hold a target frame rate, for frontends without vsync
+pub struct FrameLimiter {
+    frame_duration: Duration,
+    next_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    pub fn new(target_fps: f64) -> Self {
+        FrameLimiter {
+            frame_duration: Duration::from_secs_f64(1.0 / target_fps),
+            next_frame: None,
+        }
+    }
+
+    /// Blocks until the current frame's time slot is over. The first call returns at once
+    /// and starts the clock.
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        let deadline = match self.next_frame {
+            Some(deadline) => deadline,
+            None => {
+                self.next_frame = Some(now + self.frame_duration);
+                return;
+            }
+        };
+
+        if now < deadline {
+            thread::sleep(deadline - now);
+            self.next_frame = Some(deadline + self.frame_duration);
+        } else {
+            // Running late: start over from now rather than rushing frames to catch up
+            self.next_frame = Some(now + self.frame_duration);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use bitflags::bitflags;
+
+bitflags! {
+    // Shifted out of $4016/$4017 starting with A
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct JoypadButton: u8 {
+        const RIGHT    = 0b10000000;
+        const LEFT     = 0b01000000;
+        const DOWN     = 0b00100000;
+        const UP       = 0b00010000;
+        const START    = 0b00001000;
+        const SELECT   = 0b00000100;
+        const BUTTON_B = 0b00000010;
+        const BUTTON_A = 0b00000001;
+    }
+}
+
+// The Famicom reports controller 2's microphone in bit 2 of $4016
+const MICROPHONE_BIT: u8 = 0b100;
+
+// Turbo rates are converted to frames at the NTSC rate, rounded to 60
+const FRAMES_PER_SECOND: f32 = 60.0;
+
+#[derive(Clone)]
+pub struct Joypad {
+    strobe: bool,
+    button_index: u8,
+    button_status: JoypadButton,
+    famicom: bool,
+    microphone: bool,
+    turbo: Vec<(JoypadButton, f32)>,
+    frame: u64,
+}
+
+impl Default for Joypad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Joypad {
+            strobe: false,
+            button_index: 0,
+            button_status: JoypadButton::from_bits_truncate(0),
+            famicom: false,
+            microphone: false,
+            turbo: vec![],
+            frame: 0,
+        }
+    }
+
+    pub fn write(&mut self, data: u8) {
+        self.strobe = data & 1 == 1;
+        if self.strobe {
+            self.button_index = 0
+        }
+    }
+
+    pub fn read(&mut self) -> u8 {
+        if self.button_index > 7 {
+            return 1;
+        }
+        let buttons = self.reported_buttons();
+        let response = (buttons.bits() & (1 << self.button_index)) >> self.button_index;
+        if !self.strobe && self.button_index <= 7 {
+            self.button_index += 1;
+        }
+        response
+    }
+
+    /// The bit the next `read` returns, without shifting to the next button
+    pub fn peek(&self) -> u8 {
+        match self.button_index {
+            0..=7 => (self.reported_buttons().bits() >> self.button_index) & 1,
+            _ => 1,
+        }
+    }
+
+    pub fn set_button_pressed_status(&mut self, button: JoypadButton, pressed: bool) {
+        self.button_status.set(button, pressed);
+    }
+
+    /// Makes `button` rapid-fire while held, pressed and released `rate_hz` times a
+    /// second. The phase follows `advance_frame`, not wall time, so replays stay in sync.
+    pub fn set_turbo(&mut self, button: JoypadButton, rate_hz: f32) {
+        self.clear_turbo(button);
+        self.turbo.push((button, rate_hz));
+    }
+
+    pub fn clear_turbo(&mut self, button: JoypadButton) {
+        self.turbo
+            .retain(|(turbo_button, _)| *turbo_button != button);
+    }
+
+    /// Moves turbo buttons on by one video frame
+    pub fn advance_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    // Held buttons, minus the turbo ones in the released half of their cycle
+    fn reported_buttons(&self) -> JoypadButton {
+        let mut buttons = self.button_status;
+        for &(button, rate_hz) in &self.turbo {
+            let half_cycles = (self.frame as f32 * rate_hz * 2.0 / FRAMES_PER_SECOND) as u64;
+            if half_cycles % 2 == 1 {
+                buttons.remove(button);
+            }
+        }
+        buttons
+    }
+
+    /// Famicom hard-wired controllers, which have the microphone on controller 2
+    pub fn set_famicom_mode(&mut self, famicom: bool) {
+        self.famicom = famicom;
+    }
+
+    pub fn set_microphone(&mut self, active: bool) {
+        self.microphone = active;
+    }
+
+    /// Microphone state as it appears in a $4016 read, zero outside Famicom mode
+    pub fn microphone_bit(&self) -> u8 {
+        match self.famicom && self.microphone {
+            true => MICROPHONE_BIT,
+            false => 0,
+        }
+    }
+}
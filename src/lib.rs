@@ -1,8 +1,17 @@
 pub use cpu::CPU;
 pub use cpu::Memory;
 
+pub mod apu;
 pub mod bus;
 pub mod cartridge;
 pub mod cpu;
+pub mod disasm;
+#[cfg(feature = "std")]
+pub mod frame_limiter;
+pub mod joypad;
+pub mod mapper;
+pub mod nes;
 pub mod opcodes;
 pub mod ppu;
+pub mod region;
+pub mod render;
@@ -1,8 +1,17 @@
+pub mod apu;
 pub mod bus;
 pub mod cartridge;
 pub mod cpu;
+pub mod disasm;
+#[cfg(feature = "std")]
+pub mod frame_limiter;
+pub mod joypad;
+pub mod mapper;
+pub mod nes;
 pub mod opcodes;
 pub mod ppu;
+pub mod region;
+pub mod render;
 
 use bus::Bus;
 use cartridge::Rom;
@@ -30,7 +39,7 @@ fn color(byte: u8) -> Color {
     }
 }
 
-fn read_screen_state(cpu: &CPU, frame: &mut [u8; 32 * 3 * 32]) -> bool {
+fn read_screen_state(cpu: &mut CPU, frame: &mut [u8; 32 * 3 * 32]) -> bool {
     let mut frame_idx = 0;
     let mut update = false;
     for i in 0x0200..0x600 {
@@ -107,7 +116,7 @@ fn main() {
     let bytes: Vec<u8> = std::fs::read("roms/nestest.nes").unwrap();
     let rom = Rom::new(&bytes).unwrap();
 
-    let bus = Bus::new(rom);
+    let bus = Bus::new(rom).unwrap();
     let mut cpu = CPU::new(bus);
     cpu.reset();
 
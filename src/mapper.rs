@@ -0,0 +1,766 @@
+use crate::cartridge::{CHR_ROM_PAGE_SIZE, Mirroring, PRG_ROM_PAGE_SIZE, Rom, RomError};
+use lazy_static::lazy_static;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A bank of cartridge memory mapped into a CPU or PPU address window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BankWindow {
+    pub start: u16,
+    pub size: usize,
+    /// Index of the physical bank, counted in units of `size`
+    pub bank: usize,
+}
+
+/// Banks currently mapped into the PRG ($8000-$FFFF) and CHR ($0000-$1FFF) windows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankState {
+    pub prg: Vec<BankWindow>,
+    pub chr: Vec<BankWindow>,
+}
+
+/// Cartridge hardware shared between the CPU bus and the PPU
+pub trait Mapper {
+    /// CPU read from cartridge space ($8000-$FFFF)
+    fn cpu_read(&self, addr: u16) -> u8;
+
+    /// CPU write to cartridge space ($8000-$FFFF)
+    fn cpu_write(&mut self, addr: u16, data: u8);
+
+    /// PPU read from the pattern tables ($0000-$1FFF)
+    fn chr_read(&self, addr: u16) -> u8;
+
+    /// PPU write to the pattern tables, only boards with CHR RAM store it
+    fn chr_write(&mut self, _addr: u16, _data: u8) {}
+
+    fn mirroring(&self) -> Mirroring;
+
+    /// Pattern table address of a PPU fetch during rendering, for boards that clock
+    /// their IRQ counter from PPU A12
+    fn ppu_read_notify(&mut self, _addr: u16) {}
+
+    /// Whether the board is holding the CPU's IRQ line low
+    fn irq_pending(&self) -> bool {
+        false
+    }
+
+    /// Banks mapped into each window, for debugger displays. Defaults to a board without
+    /// bank switching: 32KB PRG and 8KB CHR, both bank 0.
+    fn current_banks(&self) -> BankState {
+        BankState {
+            prg: vec![BankWindow {
+                start: 0x8000,
+                size: 0x8000,
+                bank: 0,
+            }],
+            chr: vec![BankWindow {
+                start: 0x0000,
+                size: CHR_ROM_PAGE_SIZE,
+                bank: 0,
+            }],
+        }
+    }
+
+    /// Discrete-logic boards where the ROM drives the data bus during writes, so the
+    /// written value is ANDed with the ROM byte at the same address
+    fn has_bus_conflicts(&self) -> bool {
+        false
+    }
+
+    /// PPU nametable read ($2000-$2FFF), `None` falls back to the PPU's internal VRAM
+    fn nt_read(&self, _addr: u16) -> Option<u8> {
+        None
+    }
+
+    /// PPU nametable write ($2000-$2FFF), returns `false` to fall back to the PPU's internal VRAM
+    fn nt_write(&mut self, _addr: u16, _data: u8) -> bool {
+        false
+    }
+
+    /// Board registers and cartridge RAM, for save states. Defaults to a board with
+    /// neither.
+    fn snapshot(&self) -> Vec<u8> {
+        vec![]
+    }
+
+    /// Puts back what `snapshot` returned on the same board
+    fn restore(&mut self, _state: &[u8]) {}
+}
+
+// Save-state layout shared by the boards: their registers, then CHR RAM if they have it
+fn board_snapshot(registers: &[u8], chr: &[u8], chr_is_ram: bool) -> Vec<u8> {
+    let mut state = registers.to_vec();
+    if chr_is_ram {
+        state.extend_from_slice(chr);
+    }
+    state
+}
+
+// Copies the CHR RAM of a `board_snapshot` back and returns its registers
+fn restore_board<'a>(
+    state: &'a [u8],
+    registers: usize,
+    chr: &mut [u8],
+    chr_is_ram: bool,
+) -> &'a [u8] {
+    let (registers, chr_ram) = state.split_at(registers);
+    if chr_is_ram {
+        chr.copy_from_slice(chr_ram);
+    }
+    registers
+}
+
+fn mirroring_to_byte(mirroring: Mirroring) -> u8 {
+    match mirroring {
+        Mirroring::Vertical => 0,
+        Mirroring::Horizontal => 1,
+        Mirroring::FourScreen => 2,
+    }
+}
+
+fn mirroring_from_byte(byte: u8) -> Mirroring {
+    match byte {
+        0 => Mirroring::Vertical,
+        1 => Mirroring::Horizontal,
+        _ => Mirroring::FourScreen,
+    }
+}
+
+type MapperConstructor = fn(&Rom) -> Rc<RefCell<dyn Mapper>>;
+
+lazy_static! {
+    // Every supported iNES mapper number and its board, new mappers only need a line here
+    static ref MAPPERS: HashMap<u8, MapperConstructor> = {
+        let mut map: HashMap<u8, MapperConstructor> = HashMap::new();
+        map.insert(0, |rom| Rc::new(RefCell::new(Nrom::new(rom))));
+        map.insert(1, |rom| Rc::new(RefCell::new(Mmc1::new(rom))));
+        map.insert(2, |rom| Rc::new(RefCell::new(Uxrom::new(rom))));
+        map.insert(3, |rom| Rc::new(RefCell::new(Cnrom::new(rom))));
+        map.insert(4, |rom| Rc::new(RefCell::new(Mmc3::new(rom))));
+        map
+    };
+}
+
+/// Whether `new_mapper` has a board for iNES mapper number `mapper`
+pub fn is_supported(mapper: u8) -> bool {
+    MAPPERS.contains_key(&mapper)
+}
+
+/// Builds the board for `rom`, failing for mapper numbers without one
+pub fn new_mapper(rom: &Rom) -> Result<Rc<RefCell<dyn Mapper>>, RomError> {
+    match MAPPERS.get(&rom.mapper) {
+        Some(constructor) => Ok(constructor(rom)),
+        None => Err(RomError::UnsupportedMapper(rom.mapper)),
+    }
+}
+
+/// Mapper 0: no bank switching, 16KB PRG ROM is mirrored into $C000-$FFFF
+pub struct Nrom {
+    // PRG ROM as seen from $8000-$FFFF, with the mirroring already applied
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    mirroring: Mirroring,
+}
+
+// Boards without CHR ROM carry 8KB of CHR RAM
+fn chr_or_ram(rom: &Rom) -> (Vec<u8>, bool) {
+    match rom.chr_rom.len() {
+        0 => (vec![0; CHR_ROM_PAGE_SIZE], true),
+        _ => (rom.chr_rom.clone(), false),
+    }
+}
+
+impl Nrom {
+    pub fn new(rom: &Rom) -> Self {
+        let mut prg_rom: Vec<u8> = rom.prg_rom.iter().copied().cycle().take(0x8000).collect();
+        prg_rom.resize(0x8000, 0);
+
+        let (chr, chr_is_ram) = chr_or_ram(rom);
+        Nrom {
+            prg_rom,
+            chr,
+            chr_is_ram,
+            mirroring: rom.screen_mirroring,
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        self.prg_rom[(addr & 0x7FFF) as usize]
+    }
+
+    // No registers on the board, writes to ROM go nowhere
+    fn cpu_write(&mut self, _addr: u16, _data: u8) {}
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr[addr as usize]
+    }
+
+    fn chr_write(&mut self, addr: u16, data: u8) {
+        if self.chr_is_ram {
+            self.chr[addr as usize] = data;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        board_snapshot(&[], &self.chr, self.chr_is_ram)
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        restore_board(state, 0, &mut self.chr, self.chr_is_ram);
+    }
+}
+
+/// Mapper 2: switchable 16KB bank at $8000-$BFFF, last bank fixed at $C000-$FFFF
+pub struct Uxrom {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    mirroring: Mirroring,
+    prg_bank: usize,
+}
+
+impl Uxrom {
+    pub fn new(rom: &Rom) -> Self {
+        let (chr, chr_is_ram) = chr_or_ram(rom);
+        Uxrom {
+            prg_rom: rom.prg_rom.clone(),
+            chr,
+            chr_is_ram,
+            mirroring: rom.screen_mirroring,
+            prg_bank: 0,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        self.prg_rom.len() / PRG_ROM_PAGE_SIZE
+    }
+}
+
+impl Mapper for Uxrom {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        let bank = match addr {
+            0x8000..=0xBFFF => self.prg_bank,
+            _ => self.prg_bank_count() - 1,
+        };
+        self.prg_rom[bank * PRG_ROM_PAGE_SIZE + (addr & 0x3FFF) as usize]
+    }
+
+    fn cpu_write(&mut self, _addr: u16, data: u8) {
+        self.prg_bank = (data & 0x0F) as usize % self.prg_bank_count();
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr[addr as usize]
+    }
+
+    fn chr_write(&mut self, addr: u16, data: u8) {
+        if self.chr_is_ram {
+            self.chr[addr as usize] = data;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        true
+    }
+
+    fn current_banks(&self) -> BankState {
+        BankState {
+            prg: vec![
+                BankWindow {
+                    start: 0x8000,
+                    size: PRG_ROM_PAGE_SIZE,
+                    bank: self.prg_bank,
+                },
+                BankWindow {
+                    start: 0xC000,
+                    size: PRG_ROM_PAGE_SIZE,
+                    bank: self.prg_bank_count() - 1,
+                },
+            ],
+            chr: vec![BankWindow {
+                start: 0x0000,
+                size: CHR_ROM_PAGE_SIZE,
+                bank: 0,
+            }],
+        }
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        board_snapshot(&[self.prg_bank as u8], &self.chr, self.chr_is_ram)
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        let registers = restore_board(state, 1, &mut self.chr, self.chr_is_ram);
+        self.prg_bank = registers[0] as usize;
+    }
+}
+
+/// Mapper 3: fixed PRG like NROM, switchable 8KB CHR bank
+pub struct Cnrom {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    mirroring: Mirroring,
+    chr_bank: usize,
+}
+
+impl Cnrom {
+    pub fn new(rom: &Rom) -> Self {
+        let mut prg_rom: Vec<u8> = rom.prg_rom.iter().copied().cycle().take(0x8000).collect();
+        prg_rom.resize(0x8000, 0);
+
+        let (chr, chr_is_ram) = chr_or_ram(rom);
+        Cnrom {
+            prg_rom,
+            chr,
+            chr_is_ram,
+            mirroring: rom.screen_mirroring,
+            chr_bank: 0,
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        self.chr_bank * CHR_ROM_PAGE_SIZE + addr as usize
+    }
+}
+
+impl Mapper for Cnrom {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        self.prg_rom[(addr & 0x7FFF) as usize]
+    }
+
+    fn cpu_write(&mut self, _addr: u16, data: u8) {
+        self.chr_bank = (data & 0x03) as usize % (self.chr.len() / CHR_ROM_PAGE_SIZE);
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr[self.chr_offset(addr)]
+    }
+
+    fn chr_write(&mut self, addr: u16, data: u8) {
+        if self.chr_is_ram {
+            let offset = self.chr_offset(addr);
+            self.chr[offset] = data;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        true
+    }
+
+    fn current_banks(&self) -> BankState {
+        BankState {
+            prg: vec![BankWindow {
+                start: 0x8000,
+                size: 0x8000,
+                bank: 0,
+            }],
+            chr: vec![BankWindow {
+                start: 0x0000,
+                size: CHR_ROM_PAGE_SIZE,
+                bank: self.chr_bank,
+            }],
+        }
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        board_snapshot(&[self.chr_bank as u8], &self.chr, self.chr_is_ram)
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        let registers = restore_board(state, 1, &mut self.chr, self.chr_is_ram);
+        self.chr_bank = registers[0] as usize;
+    }
+}
+
+const MMC1_CHR_BANK_SIZE: usize = 0x1000;
+// A 1 in bit 4 marks the register empty, it reaches bit 0 after four writes
+const MMC1_SHIFT_EMPTY: u8 = 0b1_0000;
+
+/// Mapper 1: PRG and CHR banks loaded through a 5-bit serial shift register
+pub struct Mmc1 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    mirroring: Mirroring,
+    shift_register: u8,
+    control: u8,
+    chr_bank0: u8,
+    chr_bank1: u8,
+    prg_bank: u8,
+}
+
+impl Mmc1 {
+    pub fn new(rom: &Rom) -> Self {
+        let (chr, chr_is_ram) = chr_or_ram(rom);
+        Mmc1 {
+            prg_rom: rom.prg_rom.clone(),
+            chr,
+            chr_is_ram,
+            mirroring: rom.screen_mirroring,
+            shift_register: MMC1_SHIFT_EMPTY,
+            // Powers on with the last PRG bank fixed at $C000
+            control: 0b0_1100,
+            chr_bank0: 0,
+            chr_bank1: 0,
+            prg_bank: 0,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        self.prg_rom.len() / PRG_ROM_PAGE_SIZE
+    }
+
+    /// 16KB banks at $8000 and $C000
+    fn prg_banks(&self) -> (usize, usize) {
+        let bank = (self.prg_bank & 0x0F) as usize;
+        let (low, high) = match (self.control >> 2) & 0b11 {
+            0 | 1 => (bank & !1, bank | 1),
+            2 => (0, bank),
+            _ => (bank, self.prg_bank_count() - 1),
+        };
+        (low % self.prg_bank_count(), high % self.prg_bank_count())
+    }
+
+    /// 4KB banks at $0000 and $1000
+    fn chr_banks(&self) -> (usize, usize) {
+        let bank_count = self.chr.len() / MMC1_CHR_BANK_SIZE;
+        let (low, high) = match self.control & 0b1_0000 {
+            0 => (
+                (self.chr_bank0 & !1) as usize,
+                (self.chr_bank0 | 1) as usize,
+            ),
+            _ => (self.chr_bank0 as usize, self.chr_bank1 as usize),
+        };
+        (low % bank_count, high % bank_count)
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        let (low, high) = self.chr_banks();
+        let bank = match addr {
+            0x0000..=0x0FFF => low,
+            _ => high,
+        };
+        bank * MMC1_CHR_BANK_SIZE + (addr as usize & 0x0FFF)
+    }
+
+    fn write_register(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x8000..=0x9FFF => {
+                self.control = data;
+                // One-screen mirroring (0 and 1) has no `Mirroring` variant yet
+                if self.mirroring != Mirroring::FourScreen {
+                    match data & 0b11 {
+                        2 => self.mirroring = Mirroring::Vertical,
+                        3 => self.mirroring = Mirroring::Horizontal,
+                        _ => {}
+                    }
+                }
+            }
+            0xA000..=0xBFFF => self.chr_bank0 = data,
+            0xC000..=0xDFFF => self.chr_bank1 = data,
+            _ => self.prg_bank = data,
+        }
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        let (low, high) = self.prg_banks();
+        let bank = match addr {
+            0x8000..=0xBFFF => low,
+            _ => high,
+        };
+        self.prg_rom[bank * PRG_ROM_PAGE_SIZE + (addr & 0x3FFF) as usize]
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) {
+        if data & 0b1000_0000 != 0 {
+            self.shift_register = MMC1_SHIFT_EMPTY;
+            self.control |= 0b0_1100;
+            return;
+        }
+
+        let full = self.shift_register & 1 != 0;
+        self.shift_register = (self.shift_register >> 1) | ((data & 1) << 4);
+        if full {
+            let value = self.shift_register;
+            self.write_register(addr, value);
+            self.shift_register = MMC1_SHIFT_EMPTY;
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr[self.chr_offset(addr)]
+    }
+
+    fn chr_write(&mut self, addr: u16, data: u8) {
+        if self.chr_is_ram {
+            let offset = self.chr_offset(addr);
+            self.chr[offset] = data;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn current_banks(&self) -> BankState {
+        let (prg_low, prg_high) = self.prg_banks();
+        let (chr_low, chr_high) = self.chr_banks();
+        BankState {
+            prg: vec![
+                BankWindow {
+                    start: 0x8000,
+                    size: PRG_ROM_PAGE_SIZE,
+                    bank: prg_low,
+                },
+                BankWindow {
+                    start: 0xC000,
+                    size: PRG_ROM_PAGE_SIZE,
+                    bank: prg_high,
+                },
+            ],
+            chr: vec![
+                BankWindow {
+                    start: 0x0000,
+                    size: MMC1_CHR_BANK_SIZE,
+                    bank: chr_low,
+                },
+                BankWindow {
+                    start: 0x1000,
+                    size: MMC1_CHR_BANK_SIZE,
+                    bank: chr_high,
+                },
+            ],
+        }
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        let registers = [
+            mirroring_to_byte(self.mirroring),
+            self.shift_register,
+            self.control,
+            self.chr_bank0,
+            self.chr_bank1,
+            self.prg_bank,
+        ];
+        board_snapshot(&registers, &self.chr, self.chr_is_ram)
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        let registers = restore_board(state, 6, &mut self.chr, self.chr_is_ram);
+        self.mirroring = mirroring_from_byte(registers[0]);
+        self.shift_register = registers[1];
+        self.control = registers[2];
+        self.chr_bank0 = registers[3];
+        self.chr_bank1 = registers[4];
+        self.prg_bank = registers[5];
+    }
+}
+
+const MMC3_PRG_BANK_SIZE: usize = 0x2000;
+const MMC3_CHR_BANK_SIZE: usize = 0x0400;
+
+/// Mapper 4: 8KB PRG and 1KB/2KB CHR banks, with a scanline counter clocked by PPU A12
+pub struct Mmc3 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    mirroring: Mirroring,
+    bank_select: u8,
+    bank_registers: [u8; 8],
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+    a12: bool,
+}
+
+impl Mmc3 {
+    pub fn new(rom: &Rom) -> Self {
+        let (chr, chr_is_ram) = chr_or_ram(rom);
+        Mmc3 {
+            prg_rom: rom.prg_rom.clone(),
+            chr,
+            chr_is_ram,
+            mirroring: rom.screen_mirroring,
+            bank_select: 0,
+            bank_registers: [0; 8],
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+            a12: false,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        self.prg_rom.len() / MMC3_PRG_BANK_SIZE
+    }
+
+    fn prg_bank(&self, addr: u16) -> usize {
+        let second_last = self.prg_bank_count() - 2;
+        let swapped = self.bank_select & 0b0100_0000 != 0;
+        let bank = match (addr, swapped) {
+            (0x8000..=0x9FFF, false) | (0xC000..=0xDFFF, true) => self.bank_registers[6] as usize,
+            (0x8000..=0x9FFF, true) | (0xC000..=0xDFFF, false) => second_last,
+            (0xA000..=0xBFFF, _) => self.bank_registers[7] as usize,
+            _ => self.prg_bank_count() - 1,
+        };
+        bank % self.prg_bank_count()
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        // With bit 7 set the 2KB banks move to $1000 and the 1KB banks to $0000
+        let addr = match self.bank_select & 0b1000_0000 != 0 {
+            true => addr ^ 0x1000,
+            false => addr,
+        };
+        let bank = match addr {
+            0x0000..=0x07FF => (self.bank_registers[0] & 0xFE) as usize + (addr as usize >> 10 & 1),
+            0x0800..=0x0FFF => (self.bank_registers[1] & 0xFE) as usize + (addr as usize >> 10 & 1),
+            _ => self.bank_registers[2 + ((addr as usize - 0x1000) >> 10)] as usize,
+        };
+        let bank_count = self.chr.len() / MMC3_CHR_BANK_SIZE;
+        (bank % bank_count) * MMC3_CHR_BANK_SIZE + (addr as usize & 0x3FF)
+    }
+
+    fn clock_irq_counter(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn cpu_read(&self, addr: u16) -> u8 {
+        self.prg_rom[self.prg_bank(addr) * MMC3_PRG_BANK_SIZE + (addr as usize & 0x1FFF)]
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) {
+        match (addr, addr & 1) {
+            (0x8000..=0x9FFF, 0) => self.bank_select = data,
+            (0x8000..=0x9FFF, _) => self.bank_registers[(self.bank_select & 0b111) as usize] = data,
+            (0xA000..=0xBFFF, 0) => {
+                if self.mirroring != Mirroring::FourScreen {
+                    self.mirroring = match data & 1 {
+                        0 => Mirroring::Vertical,
+                        _ => Mirroring::Horizontal,
+                    };
+                }
+            }
+            (0xA000..=0xBFFF, _) => {} // PRG RAM protect, there is no PRG RAM yet
+            (0xC000..=0xDFFF, 0) => self.irq_latch = data,
+            (0xC000..=0xDFFF, _) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            (_, 0) => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            }
+            (_, _) => self.irq_enabled = true,
+        }
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.chr[self.chr_offset(addr)]
+    }
+
+    fn chr_write(&mut self, addr: u16, data: u8) {
+        if self.chr_is_ram {
+            let offset = self.chr_offset(addr);
+            self.chr[offset] = data;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    // The counter is clocked on each rise of A12
+    fn ppu_read_notify(&mut self, addr: u16) {
+        let a12 = addr & 0x1000 != 0;
+        if a12 && !self.a12 {
+            self.clock_irq_counter();
+        }
+        self.a12 = a12;
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn current_banks(&self) -> BankState {
+        BankState {
+            prg: (0x8000..=0xFFFFu16)
+                .step_by(MMC3_PRG_BANK_SIZE)
+                .map(|start| BankWindow {
+                    start,
+                    size: MMC3_PRG_BANK_SIZE,
+                    bank: self.prg_bank(start),
+                })
+                .collect(),
+            chr: (0x0000..=0x1FFFu16)
+                .step_by(MMC3_CHR_BANK_SIZE)
+                .map(|start| BankWindow {
+                    start,
+                    size: MMC3_CHR_BANK_SIZE,
+                    bank: self.chr_offset(start) / MMC3_CHR_BANK_SIZE,
+                })
+                .collect(),
+        }
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        let mut registers = vec![mirroring_to_byte(self.mirroring), self.bank_select];
+        registers.extend_from_slice(&self.bank_registers);
+        registers.extend([
+            self.irq_latch,
+            self.irq_counter,
+            self.irq_reload as u8,
+            self.irq_enabled as u8,
+            self.irq_pending as u8,
+            self.a12 as u8,
+        ]);
+        board_snapshot(&registers, &self.chr, self.chr_is_ram)
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        let registers = restore_board(state, 16, &mut self.chr, self.chr_is_ram);
+        self.mirroring = mirroring_from_byte(registers[0]);
+        self.bank_select = registers[1];
+        self.bank_registers.copy_from_slice(&registers[2..10]);
+        self.irq_latch = registers[10];
+        self.irq_counter = registers[11];
+        self.irq_reload = registers[12] != 0;
+        self.irq_enabled = registers[13] != 0;
+        self.irq_pending = registers[14] != 0;
+        self.a12 = registers[15] != 0;
+    }
+}
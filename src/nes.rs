@@ -0,0 +1,242 @@
+use crate::bus::{Bus, BusState};
+use crate::cartridge::{Mirroring, Rom, RomError};
+use crate::cpu::{CPU, CpuState};
+use crate::region::Region;
+use crate::render::{self, frame::Frame};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::time::SystemTime;
+#[cfg(feature = "image")]
+use std::{fs::File, io, io::BufWriter, path::Path};
+
+// Largest speed-up or slow-down applied to keep the audio buffer near half full
+const MAX_RATE_ADJUST: f64 = 0.005;
+
+/// Which hardware quirks the emulator models, `true` for the ones it does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccuracyReport {
+    /// Unmapped reads return the last value on the data bus
+    pub open_bus: bool,
+    /// $4016/$4017 reads keep the open-bus $40 in their upper bits
+    pub controller_open_bus: bool,
+    /// JMP ($xxFF) fetches the high byte from the start of the same page
+    pub jmp_indirect_page_wrap: bool,
+    /// Indexed reads and taken branches pay extra cycles for crossing a page
+    pub page_cross_cycles: bool,
+    /// Read-modify-write instructions write the old value back before the new one
+    pub rmw_dummy_write: bool,
+    /// SHY, SHX, AHX, TAS, LAS, XAA and LAX #imm behave as measured on hardware
+    pub unstable_opcodes: bool,
+    /// NMI fires on entering vblank, or when enabled during it
+    pub vblank_nmi: bool,
+    /// The 9th sprite on a line sets the overflow flag on the dot it's found
+    pub sprite_overflow: bool,
+    /// Opaque sprite 0 pixels over opaque background set the hit flag
+    pub sprite_zero_hit: bool,
+    /// PPU register writes are ignored for the first frame after power-on
+    pub ppu_warm_up: bool,
+    /// The pre-render line is a dot short on odd frames while rendering
+    pub odd_frame_skip: bool,
+    /// Writes to boards without bus-conflict protection are ANDed with the ROM byte
+    pub bus_conflicts: bool,
+}
+
+/// Snapshot of the whole console taken by `Nes::save_state`
+#[derive(Clone)]
+pub struct SaveState {
+    cpu: CpuState,
+    bus: BusState,
+    frames: u64,
+}
+
+/// A save state kept in a quick-save slot, tagged with when and at which frame it was taken
+#[derive(Clone)]
+pub struct SaveSlot {
+    pub saved_at: SystemTime,
+    pub frames: u64,
+    state: SaveState,
+}
+
+/// The whole console: CPU, bus and everything hanging off it
+pub struct Nes {
+    pub cpu: CPU,
+    region: Region,
+    frames: u64,
+    audio: Vec<f32>,
+    frame: Frame,
+    slots: HashMap<u8, SaveSlot>,
+}
+
+impl Nes {
+    /// Fails when the ROM's mapper has no board
+    pub fn new(rom: Rom) -> Result<Self, RomError> {
+        let mut cpu = CPU::new(Bus::new(rom)?);
+        cpu.reset();
+        Ok(Nes {
+            cpu,
+            region: Region::default(),
+            frames: 0,
+            audio: vec![],
+            frame: Frame::new(),
+            slots: HashMap::new(),
+        })
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// Switches the TV system for both frame timing and the CPU/PPU clock ratio
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+        self.cpu.bus.set_region(region);
+    }
+
+    /// Video frames run since power-on
+    pub fn frame_count(&self) -> u64 {
+        self.frames
+    }
+
+    /// CPU registers, RAM, PPU, APU, controllers and mapper state at this point
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            cpu: self.cpu.save_state(),
+            bus: self.cpu.bus.save_state(),
+            frames: self.frames,
+        }
+    }
+
+    /// Returns to a `save_state` of this same cartridge
+    pub fn load_state(&mut self, state: &SaveState) {
+        self.cpu.load_state(&state.cpu);
+        self.cpu.bus.load_state(&state.bus);
+        self.region = self.cpu.bus.region();
+        self.frames = state.frames;
+    }
+
+    /// Saves the current state into `slot`, replacing what was there
+    pub fn save_state_slot(&mut self, slot: u8) {
+        let saved = SaveSlot {
+            saved_at: SystemTime::now(),
+            frames: self.frames,
+            state: self.save_state(),
+        };
+        self.slots.insert(slot, saved);
+    }
+
+    /// Loads the state saved in `slot`, returns `false` and changes nothing if it's empty
+    pub fn load_state_slot(&mut self, slot: u8) -> bool {
+        match self.slots.get(&slot).cloned() {
+            Some(saved) => {
+                self.load_state(&saved.state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Timestamp and frame count of `slot`, for listing slots in a frontend
+    pub fn save_slot(&self, slot: u8) -> Option<&SaveSlot> {
+        self.slots.get(&slot)
+    }
+
+    /// Runs one video frame and returns a copy of its picture, its audio is then in
+    /// `audio_samples`
+    pub fn step_frame(&mut self) -> Frame {
+        self.run_frame();
+        self.frame.clone()
+    }
+
+    /// Runs one video frame without copying its picture out, read it from `frame_buffer`
+    pub fn run_frame(&mut self) {
+        // Frame boundaries come from the total dot count, so the odd third of a CPU cycle
+        // per NTSC frame is carried over instead of drifting
+        self.frames += 1;
+        let end = self.frame_boundary(self.frames);
+
+        if !self.cpu.halted {
+            self.cpu
+                .run_with_mut_callback(|cpu| match cpu.cycles >= end {
+                    true => ControlFlow::Break(()),
+                    false => ControlFlow::Continue(()),
+                });
+        }
+        self.cpu.bus.joypad1.advance_frame();
+        self.cpu.bus.joypad2.advance_frame();
+        self.audio = self.cpu.bus.take_samples();
+
+        render::render(self.cpu.bus.ppu(), &mut self.frame);
+    }
+
+    /// RGB bytes of the last frame run, 256x240 pixels of 3 bytes each
+    pub fn frame_buffer(&self) -> &[u8] {
+        &self.frame.data
+    }
+
+    /// Samples produced during the last `step_frame`, at 44.1kHz
+    pub fn audio_samples(&self) -> &[f32] {
+        &self.audio
+    }
+
+    // CPU cycle at which frame `frame` starts
+    fn frame_boundary(&self, frame: u64) -> u64 {
+        self.region
+            .ppu_to_cpu_cycles(frame * self.region.dots_per_frame())
+    }
+
+    /// CPU cycles a frontend should run during one host frame at `host_refresh_hz`.
+    /// Runs slightly faster while the audio buffer is below half of `capacity` samples
+    /// and slightly slower above it, so playback neither underruns nor overruns.
+    pub fn cycles_for_host_frame(
+        &self,
+        host_refresh_hz: f64,
+        buffered: usize,
+        capacity: usize,
+    ) -> u64 {
+        let base = self.region.cpu_clock_hz() / host_refresh_hz;
+        let fill = match capacity {
+            0 => 0.5,
+            _ => (buffered as f64 / capacity as f64).min(1.0),
+        };
+        let adjust = 1.0 + MAX_RATE_ADJUST * (1.0 - 2.0 * fill);
+        (base * adjust).round() as u64
+    }
+
+    /// Forces the nametable mirroring, for testing or for ROMs whose header has it wrong
+    pub fn override_mirroring(&mut self, mirroring: Mirroring) {
+        self.cpu.bus.override_mirroring(mirroring);
+    }
+
+    /// What this build emulates of the hardware's quirks
+    pub fn accuracy_report() -> AccuracyReport {
+        AccuracyReport {
+            open_bus: false,
+            controller_open_bus: true,
+            jmp_indirect_page_wrap: true,
+            page_cross_cycles: true,
+            rmw_dummy_write: true,
+            unstable_opcodes: true,
+            vblank_nmi: true,
+            sprite_overflow: true,
+            sprite_zero_hit: false,
+            ppu_warm_up: false,
+            odd_frame_skip: false,
+            bus_conflicts: true,
+        }
+    }
+
+    /// Renders nametable `table` (0-3) in full, without scroll, as a 256x240 frame
+    pub fn render_nametable(&self, table: u8) -> Frame {
+        let mut frame = Frame::new();
+        render::render_nametable(self.cpu.bus.ppu(), table, &mut frame);
+        frame
+    }
+
+    /// Saves nametable `table` (0-3) as a 256x240 PNG, for ripping levels
+    #[cfg(feature = "image")]
+    pub fn export_nametable_png<P: AsRef<Path>>(&self, table: u8, path: P) -> io::Result<()> {
+        let frame = self.render_nametable(table);
+        let mut out = BufWriter::new(File::create(path)?);
+        render::png::write_png(&mut out, &frame)
+    }
+}
@@ -13,19 +13,52 @@ pub struct OpCode {
 impl OpCode {
     fn new(code: u8, name: &'static str, len: u8, cycles: u8, mode: AddressingMode) -> Self {
         OpCode {
-            code: code,
-            name: name,
-            len: len,
-            cycles: cycles,
-            mode: mode,
+            code,
+            name,
+            len,
+            cycles,
+            mode,
         }
     }
+
+    /// Undocumented opcodes, named with a leading `*` as in nestest logs. KIL stays
+    /// official here since test programs use it to stop `run`.
+    pub fn is_unofficial(&self) -> bool {
+        self.name.starts_with('*')
+    }
+
+    /// Unofficial opcodes whose behaviour depends on the chip or on bus timing, and
+    /// which are only approximated. Real code can't rely on them.
+    pub fn is_unstable(&self) -> bool {
+        UNSTABLE_OPCODES.contains(&self.code)
+    }
+
+    /// Reads that take an extra cycle when indexing crosses a page. Stores and
+    /// read-modify-writes always spend that cycle, so it's in their base count.
+    pub fn has_page_cross_penalty(&self) -> bool {
+        matches!(
+            self.name.trim_start_matches('*'),
+            "ADC"
+                | "SBC"
+                | "AND"
+                | "EOR"
+                | "ORA"
+                | "CMP"
+                | "LDA"
+                | "LDX"
+                | "LDY"
+                | "LAX"
+                | "LAS"
+                | "NOP"
+        )
+    }
 }
 
 lazy_static! {
     pub static ref CPU_OPS_CODES: Vec<OpCode> = vec![
-        OpCode::new(0x00, "BRK", 1, 7, AddressingMode::NoneAddressing),
-        OpCode::new(0xea, "NOP", 1, 2, AddressingMode::NoneAddressing),
+        OpCode::new(0x00, "BRK", 1, 7, AddressingMode::Implied),
+        OpCode::new(0xea, "NOP", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x02, "KIL", 1, 2, AddressingMode::Implied), //Unofficial, halts the CPU
 
         /* Arithmetic */
         OpCode::new(0x69, "ADC", 2, 2, AddressingMode::Immediate),
@@ -74,25 +107,25 @@ lazy_static! {
         OpCode::new(0x11, "ORA", 2, 5/*+1 if page crossed*/, AddressingMode::Indirect_Y),
 
         /* Shifts */
-        OpCode::new(0x0a, "ASL", 1, 2, AddressingMode::NoneAddressing),
+        OpCode::new(0x0a, "ASL", 1, 2, AddressingMode::Accumulator),
         OpCode::new(0x06, "ASL", 2, 5, AddressingMode::ZeroPage),
         OpCode::new(0x16, "ASL", 2, 6, AddressingMode::ZeroPage_X),
         OpCode::new(0x0e, "ASL", 3, 6, AddressingMode::Absolute),
         OpCode::new(0x1e, "ASL", 3, 7, AddressingMode::Absolute_X),
 
-        OpCode::new(0x4a, "LSR", 1, 2, AddressingMode::NoneAddressing),
+        OpCode::new(0x4a, "LSR", 1, 2, AddressingMode::Accumulator),
         OpCode::new(0x46, "LSR", 2, 5, AddressingMode::ZeroPage),
         OpCode::new(0x56, "LSR", 2, 6, AddressingMode::ZeroPage_X),
         OpCode::new(0x4e, "LSR", 3, 6, AddressingMode::Absolute),
         OpCode::new(0x5e, "LSR", 3, 7, AddressingMode::Absolute_X),
 
-        OpCode::new(0x2a, "ROL", 1, 2, AddressingMode::NoneAddressing),
+        OpCode::new(0x2a, "ROL", 1, 2, AddressingMode::Accumulator),
         OpCode::new(0x26, "ROL", 2, 5, AddressingMode::ZeroPage),
         OpCode::new(0x36, "ROL", 2, 6, AddressingMode::ZeroPage_X),
         OpCode::new(0x2e, "ROL", 3, 6, AddressingMode::Absolute),
         OpCode::new(0x3e, "ROL", 3, 7, AddressingMode::Absolute_X),
 
-        OpCode::new(0x6a, "ROR", 1, 2, AddressingMode::NoneAddressing),
+        OpCode::new(0x6a, "ROR", 1, 2, AddressingMode::Accumulator),
         OpCode::new(0x66, "ROR", 2, 5, AddressingMode::ZeroPage),
         OpCode::new(0x76, "ROR", 2, 6, AddressingMode::ZeroPage_X),
         OpCode::new(0x6e, "ROR", 3, 6, AddressingMode::Absolute),
@@ -103,16 +136,16 @@ lazy_static! {
         OpCode::new(0xee, "INC", 3, 6, AddressingMode::Absolute),
         OpCode::new(0xfe, "INC", 3, 7, AddressingMode::Absolute_X),
 
-        OpCode::new(0xe8, "INX", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0xc8, "INY", 1, 2, AddressingMode::NoneAddressing),
+        OpCode::new(0xe8, "INX", 1, 2, AddressingMode::Implied),
+        OpCode::new(0xc8, "INY", 1, 2, AddressingMode::Implied),
 
         OpCode::new(0xc6, "DEC", 2, 5, AddressingMode::ZeroPage),
         OpCode::new(0xd6, "DEC", 2, 6, AddressingMode::ZeroPage_X),
         OpCode::new(0xce, "DEC", 3, 6, AddressingMode::Absolute),
         OpCode::new(0xde, "DEC", 3, 7, AddressingMode::Absolute_X),
 
-        OpCode::new(0xca, "DEX", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0x88, "DEY", 1, 2, AddressingMode::NoneAddressing),
+        OpCode::new(0xca, "DEX", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x88, "DEY", 1, 2, AddressingMode::Implied),
 
         OpCode::new(0xc9, "CMP", 2, 2, AddressingMode::Immediate),
         OpCode::new(0xc5, "CMP", 2, 3, AddressingMode::ZeroPage),
@@ -134,22 +167,22 @@ lazy_static! {
 
         /* Branching */
 
-        OpCode::new(0x4c, "JMP", 3, 3, AddressingMode::NoneAddressing), //AddressingMode that acts as Immidiate
-        OpCode::new(0x6c, "JMP", 3, 5, AddressingMode::NoneAddressing), //AddressingMode:Indirect with 6502 bug
+        OpCode::new(0x4c, "JMP", 3, 3, AddressingMode::Absolute),
+        OpCode::new(0x6c, "JMP", 3, 5, AddressingMode::Indirect), //6502 bug: the pointer does not cross pages
 
-        OpCode::new(0x20, "JSR", 3, 6, AddressingMode::NoneAddressing),
-        OpCode::new(0x60, "RTS", 1, 6, AddressingMode::NoneAddressing),
+        OpCode::new(0x20, "JSR", 3, 6, AddressingMode::Absolute),
+        OpCode::new(0x60, "RTS", 1, 6, AddressingMode::Implied),
 
-        OpCode::new(0x40, "RTI", 1, 6, AddressingMode::NoneAddressing),
+        OpCode::new(0x40, "RTI", 1, 6, AddressingMode::Implied),
 
-        OpCode::new(0xd0, "BNE", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::NoneAddressing),
-        OpCode::new(0x70, "BVS", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::NoneAddressing),
-        OpCode::new(0x50, "BVC", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::NoneAddressing),
-        OpCode::new(0x30, "BMI", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::NoneAddressing),
-        OpCode::new(0xf0, "BEQ", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::NoneAddressing),
-        OpCode::new(0xb0, "BCS", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::NoneAddressing),
-        OpCode::new(0x90, "BCC", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::NoneAddressing),
-        OpCode::new(0x10, "BPL", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::NoneAddressing),
+        OpCode::new(0xd0, "BNE", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::Relative),
+        OpCode::new(0x70, "BVS", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::Relative),
+        OpCode::new(0x50, "BVC", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::Relative),
+        OpCode::new(0x30, "BMI", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::Relative),
+        OpCode::new(0xf0, "BEQ", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::Relative),
+        OpCode::new(0xb0, "BCS", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::Relative),
+        OpCode::new(0x90, "BCC", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::Relative),
+        OpCode::new(0x10, "BPL", 2, 2 /*(+1 if branch succeeds +2 if to a new page)*/, AddressingMode::Relative),
 
         OpCode::new(0x24, "BIT", 2, 3, AddressingMode::ZeroPage),
         OpCode::new(0x2c, "BIT", 3, 4, AddressingMode::Absolute),
@@ -196,26 +229,123 @@ lazy_static! {
 
         /* Flags clear */
 
-        OpCode::new(0xD8, "CLD", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0x58, "CLI", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0xb8, "CLV", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0x18, "CLC", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0x38, "SEC", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0x78, "SEI", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0xf8, "SED", 1, 2, AddressingMode::NoneAddressing),
-
-        OpCode::new(0xaa, "TAX", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0xa8, "TAY", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0xba, "TSX", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0x8a, "TXA", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0x9a, "TXS", 1, 2, AddressingMode::NoneAddressing),
-        OpCode::new(0x98, "TYA", 1, 2, AddressingMode::NoneAddressing),
+        OpCode::new(0xD8, "CLD", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x58, "CLI", 1, 2, AddressingMode::Implied),
+        OpCode::new(0xb8, "CLV", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x18, "CLC", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x38, "SEC", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x78, "SEI", 1, 2, AddressingMode::Implied),
+        OpCode::new(0xf8, "SED", 1, 2, AddressingMode::Implied),
+
+        OpCode::new(0xaa, "TAX", 1, 2, AddressingMode::Implied),
+        OpCode::new(0xa8, "TAY", 1, 2, AddressingMode::Implied),
+        OpCode::new(0xba, "TSX", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x8a, "TXA", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x9a, "TXS", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x98, "TYA", 1, 2, AddressingMode::Implied),
 
         /* Stack */
-        OpCode::new(0x48, "PHA", 1, 3, AddressingMode::NoneAddressing),
-        OpCode::new(0x68, "PLA", 1, 4, AddressingMode::NoneAddressing),
-        OpCode::new(0x08, "PHP", 1, 3, AddressingMode::NoneAddressing),
-        OpCode::new(0x28, "PLP", 1, 4, AddressingMode::NoneAddressing),
+        OpCode::new(0x48, "PHA", 1, 3, AddressingMode::Implied),
+        OpCode::new(0x68, "PLA", 1, 4, AddressingMode::Implied),
+        OpCode::new(0x08, "PHP", 1, 3, AddressingMode::Implied),
+        OpCode::new(0x28, "PLP", 1, 4, AddressingMode::Implied),
+
+        /* Unofficial loads */
+        OpCode::new(0xa7, "*LAX", 2, 3, AddressingMode::ZeroPage),
+        OpCode::new(0xb7, "*LAX", 2, 4, AddressingMode::ZeroPage_Y),
+        OpCode::new(0xaf, "*LAX", 3, 4, AddressingMode::Absolute),
+        OpCode::new(0xbf, "*LAX", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_Y),
+        OpCode::new(0xa3, "*LAX", 2, 6, AddressingMode::Indirect_X),
+        OpCode::new(0xb3, "*LAX", 2, 5/*+1 if page crossed*/, AddressingMode::Indirect_Y),
+
+        /* Unofficial stores */
+        OpCode::new(0x87, "*SAX", 2, 3, AddressingMode::ZeroPage),
+        OpCode::new(0x97, "*SAX", 2, 4, AddressingMode::ZeroPage_Y),
+        OpCode::new(0x8f, "*SAX", 3, 4, AddressingMode::Absolute),
+        OpCode::new(0x83, "*SAX", 2, 6, AddressingMode::Indirect_X),
+
+        /* Unofficial read-modify-writes */
+        OpCode::new(0x03, "*SLO", 2, 8, AddressingMode::Indirect_X),
+        OpCode::new(0x07, "*SLO", 2, 5, AddressingMode::ZeroPage),
+        OpCode::new(0x0f, "*SLO", 3, 6, AddressingMode::Absolute),
+        OpCode::new(0x13, "*SLO", 2, 8, AddressingMode::Indirect_Y),
+        OpCode::new(0x17, "*SLO", 2, 6, AddressingMode::ZeroPage_X),
+        OpCode::new(0x1b, "*SLO", 3, 7, AddressingMode::Absolute_Y),
+        OpCode::new(0x1f, "*SLO", 3, 7, AddressingMode::Absolute_X),
+        OpCode::new(0x23, "*RLA", 2, 8, AddressingMode::Indirect_X),
+        OpCode::new(0x27, "*RLA", 2, 5, AddressingMode::ZeroPage),
+        OpCode::new(0x2f, "*RLA", 3, 6, AddressingMode::Absolute),
+        OpCode::new(0x33, "*RLA", 2, 8, AddressingMode::Indirect_Y),
+        OpCode::new(0x37, "*RLA", 2, 6, AddressingMode::ZeroPage_X),
+        OpCode::new(0x3b, "*RLA", 3, 7, AddressingMode::Absolute_Y),
+        OpCode::new(0x3f, "*RLA", 3, 7, AddressingMode::Absolute_X),
+        OpCode::new(0x43, "*SRE", 2, 8, AddressingMode::Indirect_X),
+        OpCode::new(0x47, "*SRE", 2, 5, AddressingMode::ZeroPage),
+        OpCode::new(0x4f, "*SRE", 3, 6, AddressingMode::Absolute),
+        OpCode::new(0x53, "*SRE", 2, 8, AddressingMode::Indirect_Y),
+        OpCode::new(0x57, "*SRE", 2, 6, AddressingMode::ZeroPage_X),
+        OpCode::new(0x5b, "*SRE", 3, 7, AddressingMode::Absolute_Y),
+        OpCode::new(0x5f, "*SRE", 3, 7, AddressingMode::Absolute_X),
+        OpCode::new(0x63, "*RRA", 2, 8, AddressingMode::Indirect_X),
+        OpCode::new(0x67, "*RRA", 2, 5, AddressingMode::ZeroPage),
+        OpCode::new(0x6f, "*RRA", 3, 6, AddressingMode::Absolute),
+        OpCode::new(0x73, "*RRA", 2, 8, AddressingMode::Indirect_Y),
+        OpCode::new(0x77, "*RRA", 2, 6, AddressingMode::ZeroPage_X),
+        OpCode::new(0x7b, "*RRA", 3, 7, AddressingMode::Absolute_Y),
+        OpCode::new(0x7f, "*RRA", 3, 7, AddressingMode::Absolute_X),
+        OpCode::new(0xc3, "*DCP", 2, 8, AddressingMode::Indirect_X),
+        OpCode::new(0xc7, "*DCP", 2, 5, AddressingMode::ZeroPage),
+        OpCode::new(0xcf, "*DCP", 3, 6, AddressingMode::Absolute),
+        OpCode::new(0xd3, "*DCP", 2, 8, AddressingMode::Indirect_Y),
+        OpCode::new(0xd7, "*DCP", 2, 6, AddressingMode::ZeroPage_X),
+        OpCode::new(0xdb, "*DCP", 3, 7, AddressingMode::Absolute_Y),
+        OpCode::new(0xdf, "*DCP", 3, 7, AddressingMode::Absolute_X),
+        OpCode::new(0xe3, "*ISB", 2, 8, AddressingMode::Indirect_X),
+        OpCode::new(0xe7, "*ISB", 2, 5, AddressingMode::ZeroPage),
+        OpCode::new(0xef, "*ISB", 3, 6, AddressingMode::Absolute),
+        OpCode::new(0xf3, "*ISB", 2, 8, AddressingMode::Indirect_Y),
+        OpCode::new(0xf7, "*ISB", 2, 6, AddressingMode::ZeroPage_X),
+        OpCode::new(0xfb, "*ISB", 3, 7, AddressingMode::Absolute_Y),
+        OpCode::new(0xff, "*ISB", 3, 7, AddressingMode::Absolute_X),
+
+        /* Unofficial NOPs */
+        OpCode::new(0x1a, "*NOP", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x3a, "*NOP", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x5a, "*NOP", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x7a, "*NOP", 1, 2, AddressingMode::Implied),
+        OpCode::new(0xda, "*NOP", 1, 2, AddressingMode::Implied),
+        OpCode::new(0xfa, "*NOP", 1, 2, AddressingMode::Implied),
+        OpCode::new(0x80, "*NOP", 2, 2, AddressingMode::Immediate),
+        OpCode::new(0x82, "*NOP", 2, 2, AddressingMode::Immediate),
+        OpCode::new(0x89, "*NOP", 2, 2, AddressingMode::Immediate),
+        OpCode::new(0xc2, "*NOP", 2, 2, AddressingMode::Immediate),
+        OpCode::new(0xe2, "*NOP", 2, 2, AddressingMode::Immediate),
+        OpCode::new(0x04, "*NOP", 2, 3, AddressingMode::ZeroPage),
+        OpCode::new(0x44, "*NOP", 2, 3, AddressingMode::ZeroPage),
+        OpCode::new(0x64, "*NOP", 2, 3, AddressingMode::ZeroPage),
+        OpCode::new(0x14, "*NOP", 2, 4, AddressingMode::ZeroPage_X),
+        OpCode::new(0x34, "*NOP", 2, 4, AddressingMode::ZeroPage_X),
+        OpCode::new(0x54, "*NOP", 2, 4, AddressingMode::ZeroPage_X),
+        OpCode::new(0x74, "*NOP", 2, 4, AddressingMode::ZeroPage_X),
+        OpCode::new(0xd4, "*NOP", 2, 4, AddressingMode::ZeroPage_X),
+        OpCode::new(0xf4, "*NOP", 2, 4, AddressingMode::ZeroPage_X),
+        OpCode::new(0x0c, "*NOP", 3, 4, AddressingMode::Absolute),
+        OpCode::new(0x1c, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X),
+        OpCode::new(0x3c, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X),
+        OpCode::new(0x5c, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X),
+        OpCode::new(0x7c, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X),
+        OpCode::new(0xdc, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X),
+        OpCode::new(0xfc, "*NOP", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_X),
+
+        /* Unstable unofficial opcodes, whose results differ between chips */
+        OpCode::new(0x9c, "*SHY", 3, 5, AddressingMode::Absolute_X),
+        OpCode::new(0x9e, "*SHX", 3, 5, AddressingMode::Absolute_Y),
+        OpCode::new(0x9f, "*AHX", 3, 5, AddressingMode::Absolute_Y),
+        OpCode::new(0x93, "*AHX", 2, 6, AddressingMode::Indirect_Y),
+        OpCode::new(0x9b, "*TAS", 3, 5, AddressingMode::Absolute_Y),
+        OpCode::new(0xbb, "*LAS", 3, 4/*+1 if page crossed*/, AddressingMode::Absolute_Y),
+        OpCode::new(0x8b, "*XAA", 2, 2, AddressingMode::Immediate),
+        OpCode::new(0xab, "*LAX", 2, 2, AddressingMode::Immediate),
     ];
 
     pub static ref OPCODES_MAP: HashMap<u8, &'static OpCode> = {
@@ -226,3 +356,19 @@ lazy_static! {
         map
     };
 }
+
+/// SHY, SHX, AHX, TAS, LAS, XAA and LAX #imm
+pub const UNSTABLE_OPCODES: &[u8] = &[0x9c, 0x9e, 0x9f, 0x93, 0x9b, 0xbb, 0x8b, 0xab];
+
+// Listed in CPU_OPS_CODES but still falling through to the unimplemented arm of `CPU::run`
+const NOT_YET_IMPLEMENTED: &[u8] = &[];
+
+/// Whether `CPU::run` can execute `code`
+pub fn is_implemented(code: u8) -> bool {
+    OPCODES_MAP.contains_key(&code) && !NOT_YET_IMPLEMENTED.contains(&code)
+}
+
+/// Every opcode `CPU::run` can execute, in ascending order
+pub fn implemented() -> Vec<u8> {
+    (0..=0xFF).filter(|&code| is_implemented(code)).collect()
+}
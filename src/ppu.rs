@@ -1,43 +1,266 @@
 use crate::cartridge::Mirroring;
+use crate::mapper::Mapper;
 use bitflags::bitflags;
+use std::cell::RefCell;
+use std::rc::Rc;
 
+// Power-on $2002 is undefined on hardware; vblank and sprite overflow are the bits
+// most often found set, so those are used
+pub const POWER_ON_STATUS: u8 = 0b1010_0000;
+
+/// Visible picture size in pixels
+pub const SCREEN_WIDTH: usize = 256;
+pub const SCREEN_HEIGHT: usize = 240;
+/// Bytes of sprite memory, 4 per sprite
+pub const OAM_SIZE: usize = 256;
+pub const SPRITE_COUNT: usize = OAM_SIZE / 4;
+
+const DOTS_PER_SCANLINE: u16 = 341;
+const SCANLINES_PER_FRAME: u16 = 262;
+const VBLANK_SCANLINE: u16 = 241;
+const PRE_RENDER_SCANLINE: u16 = 261;
+const SPRITE_EVAL_START: u16 = 65;
+const SPRITE_EVAL_END: u16 = 256;
+const MAX_SPRITES_PER_LINE: u8 = 8;
+
+#[derive(Clone)]
 pub struct PPU {
-    pub chr_rom: Vec<u8>,
+    pub mapper: Rc<RefCell<dyn Mapper>>,
     pub pallete_table: [u8; 32],
     pub vram: [u8; 2048],
-    pub oam: [u8; 256],
-    pub mirroring: Mirroring,
+    pub oam: [u8; OAM_SIZE],
 
+    // Nametables 2 and 3 of four-screen boards, which carry this RAM on the cartridge,
+    // for mappers that don't map it themselves
+    four_screen_vram: [u8; 2048],
     addr_reg: AddressRegister,
-    control_reg: ControlRegister,
+    pub(crate) control_reg: ControlRegister,
+    pub(crate) mask_reg: MaskRegister,
+    status_reg: StatusRegister,
+    scroll_reg: ScrollRegister,
+    oam_addr: u8,
     internal_data_buffer: u8,
+    scanline: u16,
+    dot: u16,
+    sprite_eval: SpriteEvaluation,
+    nmi_interrupt: bool,
+    mirroring_override: Option<Mirroring>,
+}
+
+// Progress of the OAM scan for the next scanline
+#[derive(Default, Clone)]
+struct SpriteEvaluation {
+    index: u8,
+    found: u8,
+    next_dot: u16,
+}
+
+/// Every PPU register at once, for debugging overlays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PpuRegistersSnapshot {
+    pub control: u8,
+    pub mask: u8,
+    pub status: u8,
+    pub oam_addr: u8,
+    pub scroll_x: u8,
+    pub scroll_y: u8,
+    pub vram_addr: u16,
+    pub temp_vram_addr: u16,
+    pub fine_x: u8,
 }
 
 impl PPU {
-    pub fn new(chr_rom: Vec<u8>, mirroring: Mirroring) -> PPU {
+    pub fn new(mapper: Rc<RefCell<dyn Mapper>>) -> PPU {
         PPU {
-            chr_rom,
-            mirroring,
+            mapper,
             pallete_table: [0; 32],
             vram: [0; 2048],
-            oam: [0; 256],
+            oam: [0; OAM_SIZE],
+            four_screen_vram: [0; 2048],
             addr_reg: AddressRegister::new(),
             control_reg: ControlRegister::new(),
+            mask_reg: MaskRegister::new(),
+            status_reg: StatusRegister::from_bits_truncate(POWER_ON_STATUS),
+            scroll_reg: ScrollRegister::new(),
+            oam_addr: 0,
             internal_data_buffer: 0,
+            scanline: 0,
+            dot: 0,
+            sprite_eval: SpriteEvaluation::default(),
+            nmi_interrupt: false,
+            mirroring_override: None,
+        }
+    }
+
+    /// Reset button state: $2000, $2001, scroll, the write latch and the read buffer are
+    /// cleared, while $2002, OAMADDR and the VRAM address keep their values
+    pub fn reset(&mut self) {
+        self.control_reg = ControlRegister::new();
+        self.mask_reg = MaskRegister::new();
+        self.scroll_reg = ScrollRegister::new();
+        self.addr_reg.reset_latch();
+        self.internal_data_buffer = 0;
+    }
+
+    pub fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    pub fn dot(&self) -> u16 {
+        self.dot
+    }
+
+    /// Advances the PPU by `dots` PPU cycles
+    pub fn tick(&mut self, dots: u32) {
+        for _ in 0..dots {
+            if self.scanline == VBLANK_SCANLINE && self.dot == 1 {
+                self.status_reg.insert(StatusRegister::VBLANK_STARTED);
+                if self.control_reg.contains(ControlRegister::GENERATE_NMI) {
+                    self.nmi_interrupt = true;
+                }
+            }
+            if self.scanline == PRE_RENDER_SCANLINE && self.dot == 1 {
+                self.status_reg.remove(StatusRegister::VBLANK_STARTED);
+                self.status_reg.remove(StatusRegister::SPRITE_OVERFLOW);
+            }
+            self.fetch_patterns();
+            self.evaluate_sprites();
+
+            self.dot += 1;
+            if self.dot == DOTS_PER_SCANLINE {
+                self.dot = 0;
+                self.scanline = (self.scanline + 1) % SCANLINES_PER_FRAME;
+            }
         }
     }
 
+    // Pattern table fetches on the current dot, reported to the mapper for boards that
+    // watch A12 (MMC3). Either background or sprites enabled is enough for the PPU to do
+    // every fetch: background tiles on dots 1-256 and 321-336, sprites on 257-320.
+    fn fetch_patterns(&mut self) {
+        let rendering = self.is_background_enabled() || self.is_sprites_enabled();
+        if !rendering
+            || (self.scanline >= SCREEN_HEIGHT as u16 && self.scanline != PRE_RENDER_SCANLINE)
+        {
+            return;
+        }
+
+        // Low and high pattern planes are fetched on the 5th and 7th dot of each 8 dot slot
+        let (slot_dot, base) = match self.dot {
+            1..=256 | 321..=336 => (
+                (self.dot - 1) % 8,
+                self.control_reg.background_pattern_addr(),
+            ),
+            257..=320 => ((self.dot - 257) % 8, self.control_reg.sprite_fetch_addr()),
+            _ => return,
+        };
+        match slot_dot {
+            4 => self.mapper.borrow_mut().ppu_read_notify(base),
+            6 => self.mapper.borrow_mut().ppu_read_notify(base + 8),
+            _ => {}
+        }
+    }
+
+    // Sprite evaluation for the next line runs over dots 65-256 of each visible line. An
+    // entry out of range takes 2 dots; one in range takes 8, as its other 3 bytes are
+    // copied to secondary OAM. The 9th sprite in range sets the overflow flag on the dot
+    // it's read. The hardware's buggy diagonal OAM scan after the 8th hit isn't modelled.
+    fn evaluate_sprites(&mut self) {
+        let rendering = self.is_background_enabled() || self.is_sprites_enabled();
+        if !rendering || self.scanline >= SCREEN_HEIGHT as u16 {
+            return;
+        }
+
+        if self.dot == SPRITE_EVAL_START {
+            self.sprite_eval = SpriteEvaluation {
+                index: 0,
+                found: 0,
+                next_dot: SPRITE_EVAL_START,
+            };
+        }
+        let eval = &mut self.sprite_eval;
+        if self.dot > SPRITE_EVAL_END
+            || self.dot != eval.next_dot
+            || eval.index as usize >= SPRITE_COUNT
+        {
+            return;
+        }
+
+        let y = self.oam[eval.index as usize * 4] as u16;
+        let in_range =
+            self.scanline >= y && self.scanline - y < self.control_reg.sprite_size() as u16;
+        eval.index += 1;
+        if !in_range {
+            eval.next_dot += 2;
+        } else if eval.found < MAX_SPRITES_PER_LINE {
+            eval.found += 1;
+            eval.next_dot += 8;
+        } else {
+            self.status_reg.insert(StatusRegister::SPRITE_OVERFLOW);
+            eval.index = SPRITE_COUNT as u8;
+        }
+    }
+
+    pub fn registers_snapshot(&self) -> PpuRegistersSnapshot {
+        PpuRegistersSnapshot {
+            control: self.control_reg.bits(),
+            mask: self.mask_reg.bits(),
+            status: self.status_reg.bits(),
+            oam_addr: self.oam_addr,
+            scroll_x: self.scroll_reg.scroll_x,
+            scroll_y: self.scroll_reg.scroll_y,
+            vram_addr: self.addr_reg.get(),
+            temp_vram_addr: self.addr_reg.temp(),
+            fine_x: self.scroll_reg.fine_x(),
+        }
+    }
+
+    /// VRAM cells that differ from `previous`, as (nametable address, old, new)
+    pub fn vram_diff(&self, previous: &[u8; 2048]) -> Vec<(u16, u8, u8)> {
+        let second_table = match self.mirroring() {
+            Mirroring::Horizontal => 0x2800,
+            _ => 0x2400,
+        };
+        self.vram
+            .iter()
+            .zip(previous.iter())
+            .enumerate()
+            .filter(|(_, (new, old))| new != old)
+            .map(|(i, (&new, &old))| {
+                let addr = match i {
+                    0..0x400 => 0x2000 + i as u16,
+                    _ => second_table + (i - 0x400) as u16,
+                };
+                (addr, old, new)
+            })
+            .collect()
+    }
+
+    /// Forces the nametable mirroring regardless of what the mapper asks for, `None`
+    /// hands it back to the mapper
+    pub fn override_mirroring(&mut self, mirroring: Option<Mirroring>) {
+        self.mirroring_override = mirroring;
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring_override
+            .unwrap_or_else(|| self.mapper.borrow().mirroring())
+    }
+
     // Horizontal:
     //   [ A ] [ a ]
     //   [ B ] [ b ]
     // Vertical:
     //   [ A ] [ B ]
     //   [ a ] [ b ]
+    // Four-screen:
+    //   [ A ] [ B ]
+    //   [ C ] [ D ]   C and D past the end of `vram`
     pub fn mirror_vram_addr(&self, addr: u16) -> u16 {
         let mirrored_vram = addr & 0b10111111111111; // mirror down 0x3000-0x3eff to 0x2000 - 0x2eff
         let vram_index = mirrored_vram - 0x2000; // to vram vector
         let name_table = vram_index / 0x400; // to the name table index
-        match (&self.mirroring, name_table) {
+        match (self.mirroring(), name_table) {
             (Mirroring::Vertical, 2) | (Mirroring::Vertical, 3) => vram_index - 0x800,
             (Mirroring::Horizontal, 2) => vram_index - 0x400,
             (Mirroring::Horizontal, 1) => vram_index - 0x400,
@@ -46,12 +269,123 @@ impl PPU {
         }
     }
 
+    // Only four-screen boards have nametables past the PPU's own 2KB, bad mirroring math
+    // would otherwise silently land in the cartridge VRAM. Checked in release builds too.
+    fn vram_index(&self, addr: u16) -> usize {
+        let index = self.mirror_vram_addr(addr) as usize;
+        let len = match self.mirroring() {
+            Mirroring::FourScreen => self.vram.len() + self.four_screen_vram.len(),
+            _ => self.vram.len(),
+        };
+        assert!(
+            index < len,
+            "nametable address {:#06x} mirrored to VRAM index {:#06x}, past the {} bytes of PPU VRAM",
+            addr,
+            index,
+            len
+        );
+        index
+    }
+
+    fn palette_index(&self, addr: u16) -> usize {
+        (addr & 0x1f) as usize
+    }
+
+    pub(crate) fn read_nametable(&self, addr: u16) -> u8 {
+        let addr = addr & 0b10111111111111;
+        if let Some(value) = self.mapper.borrow().nt_read(addr) {
+            return value;
+        }
+        let index = self.vram_index(addr);
+        match index.checked_sub(self.vram.len()) {
+            Some(cartridge_index) => self.four_screen_vram[cartridge_index],
+            None => self.vram[index],
+        }
+    }
+
+    fn write_nametable(&mut self, addr: u16, value: u8) {
+        let addr = addr & 0b10111111111111;
+        if !self.mapper.borrow_mut().nt_write(addr, value) {
+            let index = self.vram_index(addr);
+            match index.checked_sub(self.vram.len()) {
+                Some(cartridge_index) => self.four_screen_vram[cartridge_index] = value,
+                None => self.vram[index] = value,
+            }
+        }
+    }
+
     pub fn write_to_addr_reg(&mut self, value: u8) {
         self.addr_reg.update(value);
     }
 
+    /// Enabling NMI while vblank is already flagged raises one straight away
     pub fn write_to_control_reg(&mut self, value: u8) {
+        let nmi_was_enabled = self.control_reg.contains(ControlRegister::GENERATE_NMI);
         self.control_reg.update(value);
+        self.addr_reg.update_nametable(value);
+        if !nmi_was_enabled
+            && self.control_reg.contains(ControlRegister::GENERATE_NMI)
+            && self.status_reg.contains(StatusRegister::VBLANK_STARTED)
+        {
+            self.nmi_interrupt = true;
+        }
+    }
+
+    /// Takes the NMI raised since the last poll, if any
+    pub fn poll_nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi_interrupt)
+    }
+
+    pub fn write_to_mask(&mut self, value: u8) {
+        self.mask_reg.update(value);
+    }
+
+    pub fn is_background_enabled(&self) -> bool {
+        self.mask_reg.contains(MaskRegister::SHOW_BACKGROUND)
+    }
+
+    pub fn is_sprites_enabled(&self) -> bool {
+        self.mask_reg.contains(MaskRegister::SHOW_SPRITES)
+    }
+
+    pub fn write_to_scroll(&mut self, value: u8) {
+        self.scroll_reg
+            .update(value, self.addr_reg.is_first_write());
+        self.addr_reg.update_scroll(value);
+    }
+
+    /// $2002 read: clears vblank and the shared $2005/$2006 write latch
+    pub fn read_status(&mut self) -> u8 {
+        let status = self.status_reg.bits();
+        self.status_reg.remove(StatusRegister::VBLANK_STARTED);
+        self.addr_reg.reset_latch();
+        status
+    }
+
+    /// $2002 as the next read would return it, without clearing anything
+    pub fn peek_status(&self) -> u8 {
+        self.status_reg.bits()
+    }
+
+    pub fn write_to_oam_addr(&mut self, value: u8) {
+        self.oam_addr = value;
+    }
+
+    pub fn write_to_oam_data(&mut self, value: u8) {
+        self.oam[self.oam_addr as usize] = value;
+        self.oam_addr = self.oam_addr.wrapping_add(1);
+    }
+
+    /// Copies a page written to $4014 into OAM. It goes through $2004, so it starts at
+    /// OAMADDR and wraps around.
+    pub fn oam_dma(&mut self, data: &[u8; OAM_SIZE]) {
+        for &value in data.iter() {
+            self.write_to_oam_data(value);
+        }
+    }
+
+    pub fn read_oam_data(&self) -> u8 {
+        self.oam[self.oam_addr as usize]
     }
 
     fn increment_vram_addr(&mut self) {
@@ -66,42 +400,53 @@ impl PPU {
         match addr {
             0x0000..=0x1FFF => {
                 let result = self.internal_data_buffer;
-                self.internal_data_buffer = self.chr_rom[addr as usize];
+                self.internal_data_buffer = self.mapper.borrow().chr_read(addr);
                 result
             }
             0x2000..=0x2FFF => {
                 let result = self.internal_data_buffer;
-                self.internal_data_buffer = self.vram[self.mirror_vram_addr(addr) as usize];
+                self.internal_data_buffer = self.read_nametable(addr);
                 result
             }
             0x3000..=0x3EFF => {
                 let result = self.internal_data_buffer;
-                self.internal_data_buffer = self.vram[self.mirror_vram_addr(addr) as usize];
+                self.internal_data_buffer = self.read_nametable(addr);
                 result
             }
-            0x3F00..=0x3FFF => self.pallete_table[(addr & 0x1f) as usize],
+            0x3F00..=0x3FFF => self.pallete_table[self.palette_index(addr)],
             _ => panic!("PPU read from unknown address {}", addr),
         }
     }
 
+    /// $2007 as the next read would return it: the read buffer, or the palette byte
+    /// itself at $3F00-$3FFF. Neither the buffer nor the VRAM address moves.
+    pub fn peek_data(&self) -> u8 {
+        let addr = self.addr_reg.get();
+        match addr {
+            0x3F00..=0x3FFF => self.pallete_table[self.palette_index(addr)],
+            _ => self.internal_data_buffer,
+        }
+    }
+
     pub fn write_to_data_reg(&mut self, value: u8) {
         let addr = self.addr_reg.get();
         match addr {
-            0x0000..=0x1FFF => panic!("attempt to write to PPU address {:x}", addr),
+            0x0000..=0x1FFF => self.mapper.borrow_mut().chr_write(addr, value),
             0x2000..=0x2FFF => {
-                self.vram[self.mirror_vram_addr(addr) as usize] = value;
+                self.write_nametable(addr, value);
             }
             0x3000..=0x3EFF => {
-                self.vram[self.mirror_vram_addr(addr) as usize] = value;
+                self.write_nametable(addr, value);
             }
 
             //Addresses $3F10/$3F14/$3F18/$3F1C are mirrors of $3F00/$3F04/$3F08/$3F0C
             0x3F10 | 0x3F14 | 0x3F18 | 0x3F1C => {
-                let add_mirror = addr - 0x10;
-                self.pallete_table[(add_mirror & 0x1f) as usize] = value;
+                let index = self.palette_index(addr - 0x10);
+                self.pallete_table[index] = value;
             }
             0x3F00..=0x3FFF => {
-                self.pallete_table[(addr & 0x1f) as usize] = value;
+                let index = self.palette_index(addr);
+                self.pallete_table[index] = value;
             }
             _ => panic!("PPU write to unknown address {:x}", addr),
         }
@@ -109,56 +454,119 @@ impl PPU {
     }
 }
 
+#[derive(Clone)]
 pub struct AddressRegister {
-    value: (u8, u8),
+    value: u16,
+    temp: u16,
     hi_ptr: bool,
 }
 
+impl Default for AddressRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Loopy register model: `value` is the current VRAM address (v), `temp` holds
+// the address being assembled by $2006 writes (t) and `hi_ptr` is the inverse
+// of the shared write toggle (w).
 impl AddressRegister {
     pub fn new() -> AddressRegister {
         AddressRegister {
-            value: (0, 0),
+            value: 0,
+            temp: 0,
             hi_ptr: true,
         }
     }
 
     pub fn set(&mut self, value: u16) {
-        self.value.0 = (value >> 8) as u8;
-        self.value.1 = (value & 0xFF) as u8;
+        self.value = value & 0x7FFF;
     }
 
     pub fn get(&self) -> u16 {
-        (self.value.0 as u16) << 8 | self.value.1 as u16
+        self.value & 0x3FFF
+    }
+
+    pub fn temp(&self) -> u16 {
+        self.temp
     }
 
     pub fn increment(&mut self, inc: u8) {
-        let lo = self.value.1;
-        self.value.1 = self.value.1.wrapping_add(inc);
-        if lo > self.value.1 {
-            self.value.0 = self.value.0.wrapping_add(1);
-        }
+        self.value = self.value.wrapping_add(inc as u16) & 0x7FFF;
     }
 
     pub fn update(&mut self, data: u8) {
         if self.hi_ptr {
-            self.value.0 = data;
+            // Only 6 bits of the high byte are kept, bit 14 of t is cleared
+            self.temp = (self.temp & 0x00FF) | ((data & 0x3F) as u16) << 8;
         } else {
-            self.value.1 = data;
+            self.temp = (self.temp & 0xFF00) | data as u16;
+            self.value = self.temp;
         }
+        self.hi_ptr = !self.hi_ptr;
+    }
+
+    /// $2000 write: nametable select bits go to bits 10-11 of t
+    pub fn update_nametable(&mut self, data: u8) {
+        self.temp = (self.temp & !0x0C00) | ((data & 0b11) as u16) << 10;
+    }
 
-        if self.get() > 0x3FFF {
-            self.set(self.get() & 0x3FFF);
+    /// $2005 write: coarse X on the first write, coarse and fine Y on the second
+    pub fn update_scroll(&mut self, data: u8) {
+        if self.hi_ptr {
+            self.temp = (self.temp & !0x001F) | (data >> 3) as u16;
+        } else {
+            self.temp =
+                (self.temp & !0x73E0) | ((data & 0b111) as u16) << 12 | ((data >> 3) as u16) << 5;
         }
         self.hi_ptr = !self.hi_ptr;
     }
 
+    pub fn is_first_write(&self) -> bool {
+        self.hi_ptr
+    }
+
     pub fn reset_latch(&mut self) {
         self.hi_ptr = true;
     }
 }
 
+#[derive(Clone)]
+pub struct ScrollRegister {
+    pub scroll_x: u8,
+    pub scroll_y: u8,
+}
+
+impl Default for ScrollRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScrollRegister {
+    pub fn new() -> Self {
+        ScrollRegister {
+            scroll_x: 0,
+            scroll_y: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: u8, first_write: bool) {
+        if first_write {
+            self.scroll_x = data;
+        } else {
+            self.scroll_y = data;
+        }
+    }
+
+    pub fn fine_x(&self) -> u8 {
+        self.scroll_x & 0b111
+    }
+}
+
 bitflags! {
 
+   #[derive(Debug, Clone, Copy, PartialEq, Eq)]
    pub struct ControlRegister: u8 {
        const NAMETABLE1              = 0b00000001;
        const NAMETABLE2              = 0b00000010;
@@ -171,6 +579,12 @@ bitflags! {
    }
 }
 
+impl Default for ControlRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ControlRegister {
     pub fn new() -> Self {
         ControlRegister::from_bits_truncate(0b00000000)
@@ -184,7 +598,93 @@ impl ControlRegister {
         }
     }
 
+    pub fn background_pattern_addr(&self) -> u16 {
+        if !self.contains(ControlRegister::BACKROUND_PATTERN_ADDR) {
+            0
+        } else {
+            0x1000
+        }
+    }
+
+    pub fn sprite_pattern_addr(&self) -> u16 {
+        if !self.contains(ControlRegister::SPRITE_PATTERN_ADDR) {
+            0
+        } else {
+            0x1000
+        }
+    }
+
+    pub fn sprite_size(&self) -> u8 {
+        if !self.contains(ControlRegister::SPRITE_SIZE) {
+            8
+        } else {
+            16
+        }
+    }
+
+    // Pattern table used for sprite fetches; 8x16 sprites pick the table per tile, and
+    // unused slots fetch tile $FF, which sits in the $1000 table
+    fn sprite_fetch_addr(&self) -> u16 {
+        match self.sprite_size() {
+            16 => 0x1000,
+            _ => self.sprite_pattern_addr(),
+        }
+    }
+
     pub fn update(&mut self, data: u8) {
         *self = ControlRegister::from_bits_truncate(data);
     }
 }
+
+bitflags! {
+
+   #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+   pub struct MaskRegister: u8 {
+       const GREYSCALE                  = 0b00000001;
+       const LEFTMOST_8PXL_BACKGROUND   = 0b00000010;
+       const LEFTMOST_8PXL_SPRITE       = 0b00000100;
+       const SHOW_BACKGROUND            = 0b00001000;
+       const SHOW_SPRITES               = 0b00010000;
+       const EMPHASISE_RED              = 0b00100000;
+       const EMPHASISE_GREEN            = 0b01000000;
+       const EMPHASISE_BLUE             = 0b10000000;
+   }
+}
+
+impl Default for MaskRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MaskRegister {
+    pub fn new() -> Self {
+        MaskRegister::from_bits_truncate(0b00000000)
+    }
+
+    pub fn update(&mut self, data: u8) {
+        *self = MaskRegister::from_bits_truncate(data);
+    }
+}
+
+bitflags! {
+
+   #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+   pub struct StatusRegister: u8 {
+       const SPRITE_OVERFLOW  = 0b00100000;
+       const SPRITE_ZERO_HIT  = 0b01000000;
+       const VBLANK_STARTED   = 0b10000000;
+   }
+}
+
+impl Default for StatusRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatusRegister {
+    pub fn new() -> Self {
+        StatusRegister::from_bits_truncate(0b00000000)
+    }
+}
@@ -0,0 +1,41 @@
+/// TV system the console is emulating, which sets the CPU/PPU clock ratio
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum Region {
+    #[default]
+    Ntsc,
+    Pal,
+}
+
+impl Region {
+    /// CPU clock in Hz
+    pub fn cpu_clock_hz(&self) -> f64 {
+        match self {
+            Region::Ntsc => 1_789_773.0,
+            Region::Pal => 1_662_607.0,
+        }
+    }
+
+    /// PPU dots in one video frame: 262 scanlines on NTSC, 312 on PAL, 341 dots each
+    pub fn dots_per_frame(&self) -> u64 {
+        match self {
+            Region::Ntsc => 262 * 341,
+            Region::Pal => 312 * 341,
+        }
+    }
+
+    /// PPU dots elapsed after `cycles` CPU cycles (3 on NTSC, 3.2 on PAL rounded down)
+    pub fn cpu_to_ppu_cycles(&self, cycles: u64) -> u64 {
+        match self {
+            Region::Ntsc => cycles * 3,
+            Region::Pal => cycles * 16 / 5,
+        }
+    }
+
+    /// Whole CPU cycles elapsed after `dots` PPU dots
+    pub fn ppu_to_cpu_cycles(&self, dots: u64) -> u64 {
+        match self {
+            Region::Ntsc => dots / 3,
+            Region::Pal => dots * 5 / 16,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+pub mod frame;
+pub mod palette;
+#[cfg(feature = "image")]
+pub mod png;
+
+use crate::ppu::{MaskRegister, PPU};
+use frame::Frame;
+
+const NAMETABLE_SIZE: u16 = 0x400;
+const ATTRIBUTE_TABLE_OFFSET: u16 = 0x3C0;
+
+/// Pixels of one tile row, left to right, as 2-bit colour indices. `plane_lo` holds bit 0
+/// of each pixel and `plane_hi` bit 1, most significant bit leftmost.
+pub fn decode_tile_row(plane_lo: u8, plane_hi: u8) -> [u8; 8] {
+    let mut pixels = [0; 8];
+    for (x, pixel) in pixels.iter_mut().enumerate() {
+        let bit = 7 - x;
+        *pixel = (plane_hi >> bit & 1) << 1 | (plane_lo >> bit & 1);
+    }
+    pixels
+}
+
+/// All 8 rows of a tile from its 16 CHR bytes, the low plane first
+pub fn decode_tile(chr: &[u8; 16]) -> [[u8; 8]; 8] {
+    let mut rows = [[0; 8]; 8];
+    for (y, row) in rows.iter_mut().enumerate() {
+        *row = decode_tile_row(chr[y], chr[y + 8]);
+    }
+    rows
+}
+
+fn bg_pallette(ppu: &PPU, nametable_base: u16, tile_column: usize, tile_row: usize) -> [u8; 4] {
+    // Each attribute byte covers a 4x4 tile area, two bits per 2x2 quadrant
+    let attr_table_idx = tile_row / 4 * 8 + tile_column / 4;
+    let attr_byte =
+        ppu.read_nametable(nametable_base + ATTRIBUTE_TABLE_OFFSET + attr_table_idx as u16);
+
+    let pallet_idx = match (tile_column % 4 / 2, tile_row % 4 / 2) {
+        (0, 0) => attr_byte & 0b11,
+        (1, 0) => (attr_byte >> 2) & 0b11,
+        (0, 1) => (attr_byte >> 4) & 0b11,
+        (1, 1) => (attr_byte >> 6) & 0b11,
+        (_, _) => unreachable!(),
+    };
+
+    let pallete_start = 1 + (pallet_idx as usize) * 4;
+    [
+        ppu.pallete_table[0],
+        ppu.pallete_table[pallete_start],
+        ppu.pallete_table[pallete_start + 1],
+        ppu.pallete_table[pallete_start + 2],
+    ]
+}
+
+/// Draws the picture for the current frame: the nametable selected in $2000, with the
+/// left 8 pixels clipped as PPUMASK asks. With the background disabled only the backdrop
+/// colour is left.
+pub fn render(ppu: &PPU, frame: &mut Frame) {
+    if !ppu.is_background_enabled() {
+        fill_backdrop(ppu, frame, Frame::WIDTH);
+        return;
+    }
+    render_nametable(ppu, ppu.control_reg.bits() & 0b11, frame);
+    clip_left_column(ppu, frame);
+}
+
+// Games hide the left column to cover scroll artifacts; what's hidden shows the backdrop.
+// Sprites aren't drawn yet, so their clip bit has nothing to hide.
+fn clip_left_column(ppu: &PPU, frame: &mut Frame) {
+    if ppu
+        .mask_reg
+        .contains(MaskRegister::LEFTMOST_8PXL_BACKGROUND)
+    {
+        return;
+    }
+    fill_backdrop(ppu, frame, 8);
+}
+
+// Paints the leftmost `width` pixels of every line with the backdrop colour
+fn fill_backdrop(ppu: &PPU, frame: &mut Frame, width: usize) {
+    let backdrop = palette::SYSTEM_PALLETE[(ppu.pallete_table[0] & 0x3F) as usize];
+    for y in 0..Frame::HEIGHT {
+        for x in 0..width {
+            frame.set_pixel(x, y, backdrop);
+        }
+    }
+}
+
+/// Draws the whole nametable `table` (0-3) with the current CHR and palettes, ignoring scroll
+pub fn render_nametable(ppu: &PPU, table: u8, frame: &mut Frame) {
+    let nametable_base = 0x2000 + (table as u16 & 0b11) * NAMETABLE_SIZE;
+    let bank = ppu.control_reg.background_pattern_addr();
+    let mapper = ppu.mapper.borrow();
+
+    for i in 0..ATTRIBUTE_TABLE_OFFSET {
+        let tile_column = (i % 32) as usize;
+        let tile_row = (i / 32) as usize;
+        let tile = ppu.read_nametable(nametable_base + i) as u16;
+        let palette = bg_pallette(ppu, nametable_base, tile_column, tile_row);
+
+        for y in 0..8 {
+            let row = decode_tile_row(
+                mapper.chr_read(bank + tile * 16 + y),
+                mapper.chr_read(bank + tile * 16 + y + 8),
+            );
+
+            for (x, &value) in row.iter().enumerate() {
+                let rgb = palette::SYSTEM_PALLETE[(palette[value as usize] & 0x3F) as usize];
+                frame.set_pixel(tile_column * 8 + x, tile_row * 8 + y as usize, rgb);
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod test {
+    use nerust_jg::apu::{Apu, Pulse, PulseChannel};
+
+    // ============================================================================
+    // Helper Functions
+    // ============================================================================
+
+    /// Pulse channel with the given timer period and sweep register value
+    fn create_pulse(channel: PulseChannel, timer_period: u16, sweep: u8) -> Pulse {
+        let mut pulse = Pulse::new(channel);
+        pulse.write_timer_low((timer_period & 0xFF) as u8);
+        pulse.write_timer_high((timer_period >> 8) as u8);
+        pulse.write_sweep(sweep);
+        pulse
+    }
+
+    // ============================================================================
+    // Sweep Tests
+    // ============================================================================
+
+    #[test]
+    fn test_downward_sweep_pulse1_is_off_by_one() {
+        // Enabled, divider period 0, negate, shift 1
+        let mut pulse1 = create_pulse(PulseChannel::One, 0x100, 0b1000_1001);
+        let mut pulse2 = create_pulse(PulseChannel::Two, 0x100, 0b1000_1001);
+
+        assert_eq!(pulse1.sweep.target_period(0x100), 0x7F);
+        assert_eq!(pulse2.sweep.target_period(0x100), 0x80);
+
+        pulse1.clock_sweep();
+        pulse2.clock_sweep();
+        assert_eq!(pulse1.timer_period, 0x7F);
+        assert_eq!(pulse2.timer_period, 0x80);
+    }
+
+    #[test]
+    fn test_upward_sweep_is_the_same_on_both_channels() {
+        let pulse1 = create_pulse(PulseChannel::One, 0x100, 0b1000_0001);
+        let pulse2 = create_pulse(PulseChannel::Two, 0x100, 0b1000_0001);
+
+        assert_eq!(pulse1.sweep.target_period(0x100), 0x180);
+        assert_eq!(pulse2.sweep.target_period(0x100), 0x180);
+    }
+
+    #[test]
+    fn test_sweep_mutes_out_of_range_periods() {
+        // Target overflows 11 bits, even with the sweep disabled
+        let mut pulse = create_pulse(PulseChannel::Two, 0x600, 0b0000_0001);
+        assert!(pulse.is_muted());
+        pulse.clock_sweep();
+        assert_eq!(pulse.timer_period, 0x600);
+
+        // Period below 8
+        let pulse = create_pulse(PulseChannel::Two, 7, 0b0000_0000);
+        assert!(pulse.is_muted());
+
+        let pulse = create_pulse(PulseChannel::Two, 0x200, 0b0000_0001);
+        assert!(!pulse.is_muted());
+    }
+
+    #[test]
+    fn test_sweep_divider_period() {
+        // Enabled, divider period 2, shift 1
+        let mut pulse = create_pulse(PulseChannel::Two, 0x100, 0b1010_0001);
+
+        pulse.clock_sweep(); // Reload, divider was 0 so the period updates
+        assert_eq!(pulse.timer_period, 0x180);
+        pulse.clock_sweep();
+        pulse.clock_sweep();
+        assert_eq!(pulse.timer_period, 0x180);
+        pulse.clock_sweep();
+        assert_eq!(pulse.timer_period, 0x240);
+    }
+
+    // ============================================================================
+    // Sample Output Tests
+    // ============================================================================
+
+    /// Pulse 1 at 50% duty, constant volume 15, timer period 8 (18 CPU cycles per step)
+    fn create_square_wave_apu() -> Apu {
+        let mut apu = Apu::new(44100);
+        apu.write_register(0x4015, 0b01);
+        apu.write_register(0x4000, 0b1011_1111);
+        apu.write_register(0x4002, 0x08);
+        apu.write_register(0x4003, 0x08);
+        apu
+    }
+
+    #[test]
+    fn test_render_samples_is_deterministic() {
+        let first = create_square_wave_apu().render_samples(29830 * 3);
+        let second = create_square_wave_apu().render_samples(29830 * 3);
+
+        assert_eq!(first.len(), 2205); // 3 frames at 44.1kHz
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_square_wave_golden_samples() {
+        let high = 95.88 / (8128.0 / 15.0 + 100.0);
+        let samples = create_square_wave_apu().render_samples(330);
+
+        assert_eq!(samples, vec![high, 0.0, 0.0, high, high, 0.0, 0.0, high]);
+    }
+
+    #[test]
+    fn test_disabled_channel_is_silent() {
+        let mut apu = create_square_wave_apu();
+        apu.write_register(0x4015, 0b00);
+
+        assert!(apu.render_samples(1000).iter().all(|&sample| sample == 0.0));
+    }
+}
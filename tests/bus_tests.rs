@@ -0,0 +1,386 @@
+mod common;
+
+#[cfg(test)]
+mod test {
+    use crate::common::create_test_rom_with_program;
+    use nerust_jg::CPU;
+    use nerust_jg::Memory;
+    use nerust_jg::bus::{Bus, IoDevice};
+    use nerust_jg::cartridge::RomBuilder;
+    use nerust_jg::joypad::JoypadButton;
+    use nerust_jg::region::Region;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // ============================================================================
+    // Access Statistics Tests
+    // ============================================================================
+
+    #[test]
+    fn test_access_stats_disabled_by_default() {
+        let bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+
+        assert_eq!(bus.access_stats(), None);
+    }
+
+    #[test]
+    fn test_access_stats_counts_zero_page_stores() {
+        let program = vec![
+            0xa9, 0x01, // LDA #$01
+            0x85, 0x10, // STA $10
+            0x85, 0x11, // STA $11
+            0x85, 0x12, // STA $12
+            0x02, // KIL
+        ];
+        let rom = create_test_rom_with_program(program);
+        let mut cpu = CPU::new(Bus::new(rom).unwrap());
+        cpu.reset();
+        cpu.bus.enable_access_stats();
+        cpu.run();
+
+        let stats = cpu.bus.access_stats().unwrap();
+        assert_eq!(stats.ram_writes, 3);
+        assert_eq!(stats.ram_reads, 0);
+        assert_eq!(stats.cartridge_reads, 9); // Every opcode and operand byte
+        assert_eq!(stats.ppu_writes, 0);
+    }
+
+    // ============================================================================
+    // RAM Mirror Tests
+    // ============================================================================
+
+    const RAM_MIRRORS: [u16; 4] = [0x0000, 0x0800, 0x1000, 0x1800];
+
+    #[test]
+    fn test_ram_write_visible_in_every_mirror() {
+        for (i, &written) in RAM_MIRRORS.iter().enumerate() {
+            let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+            let value = 0x10 + i as u8;
+            bus.mem_write(written + 0x42, value);
+
+            for &read in RAM_MIRRORS.iter() {
+                assert_eq!(
+                    bus.mem_read(read + 0x42),
+                    value,
+                    "write to {:04x}, read from {:04x}",
+                    written + 0x42,
+                    read + 0x42
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_ram_mirror_write_overwrites_base() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        bus.mem_write(0x0000, 0x11);
+        bus.mem_write(0x1800, 0x22);
+
+        assert_eq!(bus.mem_read(0x0000), 0x22);
+        assert_eq!(bus.mem_read(0x0800), 0x22);
+    }
+
+    #[test]
+    fn test_ram_mirror_boundary() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        bus.mem_write(0x07ff, 0xaa);
+        bus.mem_write(0x0800, 0xbb);
+
+        // $0800 wraps back to $0000, not onto the last byte of RAM
+        assert_eq!(bus.mem_read(0x07ff), 0xaa);
+        assert_eq!(bus.mem_read(0x0000), 0xbb);
+        assert_eq!(bus.mem_read(0x0fff), 0xaa);
+        assert_eq!(bus.mem_read(0x1fff), 0xaa);
+    }
+
+    #[test]
+    fn test_write_slice_across_ram_mirror_boundary() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        bus.mem_write_slice(0x0780, &data); // $0780-$08AB
+
+        assert_eq!(bus.mem_read(0x0780), 0x00);
+        assert_eq!(bus.mem_read(0x07ff), 0x7f);
+        assert_eq!(bus.mem_read(0x0000), 0x80); // $0800 lands on $0000
+        assert_eq!(bus.mem_read(0x00ab), 0x2b); // Byte 299
+        assert_eq!(bus.mem_read(0x08ab), 0x2b);
+        assert_eq!(bus.mem_read(0x00ac), 0x00);
+    }
+
+    #[test]
+    fn test_write_slice_wraps_from_ffff_into_ram() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        bus.mem_write_slice(0xfffe, &[0x11, 0x22, 0x33, 0x44]); // ROM bytes are dropped
+
+        assert_eq!(bus.mem_read(0x0000), 0x33);
+        assert_eq!(bus.mem_read(0x0001), 0x44);
+        assert_eq!(bus.mem_read(0x0002), 0x00);
+    }
+
+    #[test]
+    fn test_write_slice_from_ram_into_ppu_registers() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        let mut data = vec![0xee; 0x10];
+        data.extend([0x00, 0x00, 0x00, 0x05]); // $2000-$2003, OAMADDR = 5
+        bus.mem_write_slice(0x1ff0, &data);
+
+        assert_eq!(bus.mem_read(0x07ff), 0xee);
+        assert_eq!(bus.ppu().registers_snapshot().oam_addr, 0x05);
+    }
+
+    #[test]
+    fn test_write_slice_counts_each_byte() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        bus.enable_access_stats();
+        bus.mem_write_slice(0x0200, &[1, 2, 3]);
+
+        assert_eq!(bus.access_stats().unwrap().ram_writes, 3);
+        assert_eq!(bus.mem_read(0x0202), 3);
+    }
+
+    // ============================================================================
+    // PPU Register Tests
+    // ============================================================================
+
+    #[test]
+    fn test_cpu_stores_reach_ppu_registers() {
+        let program = vec![
+            0xa2, 0x21, // LDX #$21
+            0xa0, 0x08, // LDY #$08
+            0xa9, 0x42, // LDA #$42
+            0x8e, 0x06, 0x20, // STX $2006
+            0x8c, 0x06, 0x20, // STY $2006
+            0x8d, 0x07, 0x20, // STA $2007
+            0x02, // KIL
+        ];
+        let rom = create_test_rom_with_program(program);
+        let mut cpu = CPU::new(Bus::new(rom).unwrap());
+        cpu.reset();
+        cpu.run();
+
+        let ppu = cpu.bus.ppu();
+        assert_eq!(ppu.vram[ppu.mirror_vram_addr(0x2108) as usize], 0x42);
+        assert_eq!(cpu.mem_read(0x0006), 0x00); // Where a plain RAM write to $2006 would have landed
+    }
+
+    #[test]
+    fn test_status_read_resets_address_latch() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x02])).unwrap();
+        bus.mem_write(0x2006, 0x3F); // First byte, then abandoned
+        bus.mem_read(0x2002);
+        bus.mem_write(0x2006, 0x21); // High byte again, not the low byte
+        bus.mem_write(0x2006, 0x08);
+        bus.mem_write(0x2007, 0x55);
+
+        let ppu = bus.ppu();
+        assert_eq!(ppu.registers_snapshot().vram_addr, 0x2109);
+        assert_eq!(ppu.vram[ppu.mirror_vram_addr(0x2108) as usize], 0x55);
+    }
+
+    #[test]
+    fn test_status_read_clears_vblank_entered_by_ticking() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x02])).unwrap();
+        bus.mem_read(0x2002); // Clear the power-on vblank flag
+        bus.tick(242 * 341 / 3); // Into vblank
+
+        assert_eq!(bus.mem_read(0x2002) & 0x80, 0x80);
+        assert_eq!(bus.mem_read(0x2002) & 0x80, 0x00);
+    }
+
+    #[test]
+    fn test_pulse_register_writes_reach_the_apu() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x02])).unwrap();
+        bus.mem_write(0x4015, 0b01); // Enable pulse 1
+        bus.mem_write(0x4000, 0b1011_1111); // 50% duty, constant volume 15
+        bus.mem_write(0x4002, 0x08);
+        bus.mem_write(0x4003, 0x08);
+
+        let samples = bus.apu.render_samples(330);
+        assert!(samples.iter().any(|&sample| sample != 0.0));
+    }
+
+    #[test]
+    fn test_pal_tick_runs_sixteen_dots_per_five_cycles() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x02])).unwrap();
+        bus.set_region(Region::Pal);
+        for _ in 0..5 {
+            bus.tick(1); // 3.2 dots each, the fractions carry over
+        }
+
+        assert_eq!(bus.ppu().dot(), 16);
+    }
+
+    #[test]
+    fn test_scroll_writes_share_latch_reset_by_status() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x02])).unwrap();
+        bus.mem_write(0x2005, 0x12);
+        bus.mem_write(0x2005, 0x34);
+        let snapshot = bus.ppu().registers_snapshot();
+        assert_eq!((snapshot.scroll_x, snapshot.scroll_y), (0x12, 0x34));
+
+        bus.mem_write(0x2005, 0x56); // X, leaving the latch on Y
+        bus.mem_read(0x2002);
+        bus.mem_write(0x2005, 0x78); // X again
+        let snapshot = bus.ppu().registers_snapshot();
+        assert_eq!((snapshot.scroll_x, snapshot.scroll_y), (0x78, 0x34));
+    }
+
+    #[test]
+    fn test_ppu_data_reads_lag_behind_by_one() {
+        let mut chr = vec![0; 8192];
+        chr[0x0123] = 0xAB;
+        chr[0x0124] = 0xCD;
+        let mut bus = Bus::new(RomBuilder::new().chr(chr).build()).unwrap();
+        bus.mem_write(0x2006, 0x01);
+        bus.mem_write(0x2006, 0x23);
+
+        assert_eq!(bus.mem_read(0x2007), 0x00); // Stale buffer
+        assert_eq!(bus.mem_read(0x2007), 0xAB);
+        assert_eq!(bus.mem_read(0x200F), 0xCD); // Mirror of $2007
+    }
+
+    #[test]
+    fn test_peek_ppu_data_leaves_the_read_buffer() {
+        let mut chr = vec![0; 8192];
+        chr[0x0123] = 0xAB;
+        let mut bus = Bus::new(RomBuilder::new().chr(chr).build()).unwrap();
+        bus.mem_write(0x2006, 0x01);
+        bus.mem_write(0x2006, 0x23);
+        bus.mem_read(0x2007); // Fills the buffer with $0123 and moves on to $0124
+
+        assert_eq!(bus.peek(0x2007), 0xAB);
+        assert_eq!(bus.peek(0x2007), 0xAB);
+        assert_eq!(bus.ppu().registers_snapshot().vram_addr, 0x0124);
+        assert_eq!(bus.mem_read(0x2007), 0xAB);
+    }
+
+    #[test]
+    fn test_peek_ppu_data_returns_palette_bytes_directly() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x02])).unwrap();
+        bus.mem_write(0x2006, 0x3F);
+        bus.mem_write(0x2006, 0x01);
+        bus.mem_write(0x2007, 0x2A);
+        bus.mem_write(0x2006, 0x3F);
+        bus.mem_write(0x2006, 0x01);
+
+        assert_eq!(bus.peek(0x2007), 0x2A);
+        assert_eq!(bus.ppu().registers_snapshot().vram_addr, 0x3F01);
+    }
+
+    #[test]
+    fn test_write_only_ppu_registers_read_open_bus() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x02])).unwrap();
+        bus.mem_write(0x2003, 0x10);
+        bus.mem_write(0x2004, 0x77);
+
+        assert_eq!(bus.mem_read(0x2000), 0x77);
+        assert_eq!(bus.mem_read(0x2006), 0x77);
+        bus.mem_write(0x2003, 0x10);
+        assert_eq!(bus.mem_read(0x2004), 0x77); // OAM data at $10
+    }
+
+    #[test]
+    fn test_oam_dma_copies_page_and_stalls_cpu() {
+        let program = vec![
+            0xa9, 0x02, // LDA #$02
+            0x8d, 0x14, 0x40, // STA $4014
+            0x02, // KIL
+        ];
+        let mut cpu = CPU::new(Bus::new(create_test_rom_with_program(program)).unwrap());
+        cpu.reset();
+        let page: Vec<u8> = (0..=255).map(|i: u8| i.wrapping_mul(3)).collect();
+        cpu.bus.mem_write_slice(0x0200, &page);
+        let start = cpu.cycles;
+        cpu.run();
+
+        assert_eq!(cpu.bus.ppu().oam.to_vec(), page);
+        assert_eq!(cpu.cycles - start, 2 + 4 + 513 + 2); // LDA, STA, DMA, KIL
+    }
+
+    // ============================================================================
+    // Controller Tests
+    // ============================================================================
+
+    #[test]
+    fn test_joypad1_buttons_read_serially_after_strobe() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x02])).unwrap();
+        bus.set_joypad1_button(JoypadButton::BUTTON_A, true);
+        bus.set_joypad1_button(JoypadButton::START, true);
+        bus.set_joypad1_button(JoypadButton::LEFT, true);
+        bus.mem_write(0x4016, 1);
+        bus.mem_write(0x4016, 0);
+
+        let bits: Vec<u8> = (0..8).map(|_| bus.mem_read(0x4016) & 1).collect();
+        // A, B, Select, Start, Up, Down, Left, Right
+        assert_eq!(bits, vec![1, 0, 0, 1, 0, 0, 1, 0]);
+    }
+
+    // ============================================================================
+    // IO Device Tests
+    // ============================================================================
+
+    /// Debug port that answers reads with a fixed value and logs writes
+    struct DebugPort {
+        value: u8,
+        writes: Rc<RefCell<Vec<(u16, u8)>>>,
+    }
+
+    impl IoDevice for DebugPort {
+        fn read(&mut self, _addr: u16) -> u8 {
+            self.value
+        }
+
+        fn write(&mut self, addr: u16, data: u8) {
+            self.writes.borrow_mut().push((addr, data));
+        }
+    }
+
+    #[test]
+    fn test_map_io_routes_to_device() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        assert_eq!(bus.mem_read(0x4020), 0); // Open bus default
+
+        let writes = Rc::new(RefCell::new(vec![]));
+        bus.map_io(
+            0x4020,
+            Box::new(DebugPort {
+                value: 0x5a,
+                writes: writes.clone(),
+            }),
+        );
+        bus.mem_write(0x4020, 0x12);
+        bus.mem_write(0x4021, 0x34); // Neighbouring addresses are untouched
+
+        assert_eq!(bus.mem_read(0x4020), 0x5a);
+        assert_eq!(bus.mem_read(0x4021), 0);
+        assert_eq!(*writes.borrow(), vec![(0x4020, 0x12)]);
+    }
+
+    #[test]
+    fn test_peek_io_device_reports_open_bus_without_reading() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        bus.map_io(
+            0x4020,
+            Box::new(DebugPort {
+                value: 0x5a,
+                writes: Rc::new(RefCell::new(vec![])),
+            }),
+        );
+        bus.mem_write(0x2000, 0x3c); // Latched on the PPU data bus
+
+        assert_eq!(bus.peek(0x4020), 0x3c);
+    }
+
+    #[test]
+    #[should_panic(expected = "IO devices map into $4000-$7FFF, not 0200")]
+    fn test_map_io_rejects_ram() {
+        let mut bus = Bus::new(create_test_rom_with_program(vec![0x00])).unwrap();
+        bus.map_io(
+            0x0200,
+            Box::new(DebugPort {
+                value: 0,
+                writes: Rc::new(RefCell::new(vec![])),
+            }),
+        );
+    }
+}
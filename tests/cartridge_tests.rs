@@ -1,6 +1,11 @@
 #[cfg(test)]
 mod tests {
-    use nerust_jg::cartridge::{CHR_ROM_PAGE_SIZE, Mirroring, PRG_ROM_PAGE_SIZE, Rom};
+    use nerust_jg::CPU;
+    use nerust_jg::bus::Bus;
+    use nerust_jg::cartridge::{
+        CHR_ROM_PAGE_SIZE, Format, Mirroring, PRG_ROM_PAGE_SIZE, Rom, RomBuilder, RomError,
+    };
+    use nerust_jg::mapper;
 
     // ============================================================================
     // Test ROM Builder
@@ -118,6 +123,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fds_image_is_unsupported_format() {
+        let mut test_rom = vec![
+            0x46, 0x44, 0x53, 0x1A, // FDS magic number
+            0x01, // 1 disk side
+        ];
+        test_rom.resize(16 + 65500, 0);
+
+        assert_eq!(
+            Rom::validate(&test_rom),
+            Err(RomError::UnsupportedFormat(Format::Fds))
+        );
+        assert_eq!(
+            Rom::new(&test_rom).err(),
+            Some("Famicom Disk System images are not supported".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unif_file_is_unsupported_format() {
+        let mut test_rom = b"UNIF".to_vec();
+        test_rom.extend([0x07, 0x00, 0x00, 0x00]); // Revision 7
+        test_rom.resize(32, 0);
+
+        assert_eq!(
+            Rom::validate(&test_rom),
+            Err(RomError::UnsupportedFormat(Format::Unif))
+        );
+    }
+
+    #[test]
+    fn test_unregistered_mapper_is_unsupported() {
+        let raw = RomBuilder::new().mapper(99).to_ines();
+
+        assert!(!mapper::is_supported(99));
+        assert_eq!(Rom::validate(&raw).unwrap().mapper, 99); // The header itself is fine
+        assert_eq!(
+            Rom::new(&raw).err(),
+            Some(RomError::UnsupportedMapper(99).to_string())
+        );
+    }
+
+    #[test]
+    fn test_registered_mappers_construct() {
+        for number in [0, 1, 2, 3, 4] {
+            assert!(mapper::is_supported(number));
+            let rom = RomBuilder::new()
+                .mapper(number)
+                .prg(vec![0; 32768])
+                .chr(vec![0; 8192])
+                .build();
+            assert_eq!(rom.mapper, number);
+            mapper::new_mapper(&rom).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_bus_rejects_unsupported_mapper() {
+        let rom = Rom {
+            prg_rom: vec![0; 32768],
+            chr_rom: vec![0; 8192],
+            mapper: 99,
+            screen_mirroring: Mirroring::Horizontal,
+        };
+        assert_eq!(Bus::new(rom).err(), Some(RomError::UnsupportedMapper(99)));
+    }
+
     #[test]
     fn test_ines_unsupported_version() {
         let test_rom = create_rom(TestRom {
@@ -141,4 +213,115 @@ mod tests {
             Some("NES2.0 format is not supported".to_string())
         );
     }
+
+    // ============================================================================
+    // ROM Validation Tests
+    // ============================================================================
+
+    #[test]
+    fn test_validate_reports_header_info() {
+        let test_rom = create_rom(TestRom {
+            header: vec![
+                0x4E, 0x45, 0x53, 0x1A, // NES magic number
+                0x02, // 2 PRG ROM pages
+                0x01, // 1 CHR ROM page
+                0x13, // Mapper 1, battery and vertical mirroring flags
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+            trainer: None,
+            prg_rom: vec![1; 2 * PRG_ROM_PAGE_SIZE],
+            chr_rom: vec![2; CHR_ROM_PAGE_SIZE],
+        });
+
+        let info = Rom::validate(&test_rom).unwrap();
+
+        assert_eq!(info.mapper, 1);
+        assert_eq!(info.prg_rom_size, 2 * PRG_ROM_PAGE_SIZE);
+        assert_eq!(info.chr_rom_size, CHR_ROM_PAGE_SIZE);
+        assert_eq!(info.screen_mirroring, Mirroring::Vertical);
+        assert!(info.has_battery);
+        assert!(!info.has_trainer);
+    }
+
+    #[test]
+    fn test_validate_truncated_file() {
+        let test_rom = create_rom(TestRom {
+            header: vec![
+                0x4E, 0x45, 0x53, 0x1A, // NES magic number
+                0x02, // 2 PRG ROM pages
+                0x01, // 1 CHR ROM page
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+            trainer: None,
+            prg_rom: vec![1; PRG_ROM_PAGE_SIZE], // Only half of the declared PRG ROM
+            chr_rom: vec![],
+        });
+
+        assert_eq!(
+            Rom::validate(&test_rom),
+            Err(RomError::Truncated {
+                expected: 16 + 2 * PRG_ROM_PAGE_SIZE + CHR_ROM_PAGE_SIZE,
+                actual: 16 + PRG_ROM_PAGE_SIZE,
+            })
+        );
+        assert!(Rom::new(&test_rom).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_empty_prg_rom() {
+        let test_rom = create_rom(TestRom {
+            header: vec![
+                0x4E, 0x45, 0x53, 0x1A, // NES magic number
+                0x00, // No PRG ROM pages
+                0x01, // 1 CHR ROM page
+                0x20, // Mapper 2 (UxROM)
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+            trainer: None,
+            prg_rom: vec![],
+            chr_rom: vec![2; CHR_ROM_PAGE_SIZE],
+        });
+
+        assert_eq!(Rom::validate(&test_rom), Err(RomError::EmptyPrgRom));
+        assert_eq!(
+            Rom::new(&test_rom).err(),
+            Some("Header declares no PRG ROM".to_string())
+        );
+    }
+
+    // ============================================================================
+    // RomBuilder Tests
+    // ============================================================================
+
+    #[test]
+    fn test_rom_builder_reset_vector() {
+        let raw = RomBuilder::new()
+            .prg(vec![0xa9, 0x01])
+            .chr(vec![0x55; 16])
+            .mapper(2)
+            .mirroring(Mirroring::Vertical)
+            .reset_vector(0x8123)
+            .to_ines();
+
+        let info = Rom::validate(&raw).unwrap();
+        assert_eq!(info.prg_rom_size, PRG_ROM_PAGE_SIZE);
+        assert_eq!(info.chr_rom_size, CHR_ROM_PAGE_SIZE);
+        assert_eq!(info.mapper, 2);
+        assert_eq!(info.screen_mirroring, Mirroring::Vertical);
+
+        let rom = Rom::new(&raw).unwrap();
+        assert_eq!(rom.prg_rom[0], 0xa9);
+        assert_eq!(rom.prg_rom[0x3FFC], 0x23);
+        assert_eq!(rom.prg_rom[0x3FFD], 0x81);
+    }
+
+    #[test]
+    fn test_rom_builder_reset_vector_reads_back_through_cpu() {
+        let rom = RomBuilder::new().reset_vector(0x9abc).build();
+        let mut cpu = CPU::new(Bus::new(rom).unwrap());
+        cpu.reset();
+
+        assert_eq!(cpu.reset_vector(), 0x9abc);
+        assert_eq!(cpu.program_counter, 0x9abc);
+    }
 }
@@ -0,0 +1,6 @@
+use nerust_jg::cartridge::{Rom, RomBuilder};
+
+/// Creates a test ROM with a custom program loaded at 0x8000
+pub fn create_test_rom_with_program(program: Vec<u8>) -> Rom {
+    RomBuilder::new().prg(program).reset_vector(0x8000).build()
+}
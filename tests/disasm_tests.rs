@@ -0,0 +1,139 @@
+mod common;
+
+#[cfg(test)]
+mod test {
+    use crate::common::create_test_rom_with_program;
+
+    use nerust_jg::cpu::AddressingMode;
+    use nerust_jg::disasm::{Instruction, disassemble, disassemble_with_labels};
+    use nerust_jg::opcodes::OPCODES_MAP;
+
+    // ============================================================================
+    // Helper Functions
+    // ============================================================================
+
+    // ============================================================================
+    // Disassembly Tests
+    // ============================================================================
+
+    #[test]
+    fn test_disassemble_operands() {
+        let listing = disassemble(&[0xa9, 0x05, 0x11, 0x33, 0x0a, 0xd0, 0xfe, 0x12], 0x8000);
+
+        assert_eq!(
+            listing,
+            [
+                "8000  A9 05     LDA #$05",
+                "8002  11 33     ORA ($33),Y",
+                "8004  0A        ASL A",
+                "8005  D0 FE     BNE $8005",
+                "8007  12        .DB $12",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_decode_truncated_operand() {
+        assert!(Instruction::decode(&[0xad, 0x00], 0x8000).is_none());
+    }
+
+    #[test]
+    fn test_disassemble_with_labels() {
+        let rom = create_test_rom_with_program(vec![
+            0x20, 0x06, 0x80, // JSR $8006
+            0x4c, 0x03, 0x80, // JMP $8003
+            0xa9, 0x01, // LDA #$01
+            0x60, // RTS
+        ]);
+
+        let listing = disassemble_with_labels(&rom);
+        let lines: Vec<&str> = listing.lines().take(7).collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "8000  20 06 80  JSR L_8006",
+                "L_8003:",
+                "8003  4C 03 80  JMP L_8003",
+                "L_8006:",
+                "8006  A9 01     LDA #$01",
+                "8008  60        RTS",
+                "8009  00        BRK",
+            ]
+        );
+    }
+
+    // ============================================================================
+    // Addressing Mode Tests
+    // ============================================================================
+
+    #[test]
+    fn test_asl_accumulator_mode() {
+        let asl_a = Instruction::decode(&[0x0a], 0x8000).unwrap();
+        assert!(matches!(asl_a.opcode.mode, AddressingMode::Accumulator));
+        assert_eq!(asl_a.operand(), "A");
+
+        let asl_zp = Instruction::decode(&[0x06, 0x10], 0x8000).unwrap();
+        assert!(matches!(asl_zp.opcode.mode, AddressingMode::ZeroPage));
+        assert_eq!(asl_zp.operand(), "$10");
+    }
+
+    #[test]
+    fn test_bne_relative_target() {
+        let backward = Instruction::decode(&[0xd0, 0xfa], 0x8010).unwrap();
+        assert!(matches!(backward.opcode.mode, AddressingMode::Relative));
+        assert_eq!(backward.target(), Some(0x800c));
+        assert_eq!(backward.operand(), "$800C");
+
+        let forward = Instruction::decode(&[0xd0, 0x7f], 0x80f0).unwrap();
+        assert_eq!(forward.operand(), "$8171");
+    }
+
+    #[test]
+    fn test_operandless_classes_have_distinct_modes() {
+        let mode = |code: u8| &OPCODES_MAP.get(&code).unwrap().mode;
+
+        for code in [0xea, 0xaa, 0xe8, 0x18, 0x48, 0x60, 0x40, 0x00] {
+            assert!(
+                matches!(mode(code), AddressingMode::Implied),
+                "{:02x}",
+                code
+            );
+        }
+        for code in [0x0a, 0x4a, 0x2a, 0x6a] {
+            assert!(
+                matches!(mode(code), AddressingMode::Accumulator),
+                "{:02x}",
+                code
+            );
+        }
+        for code in [0x10, 0x30, 0x50, 0x70, 0x90, 0xb0, 0xd0, 0xf0] {
+            assert!(
+                matches!(mode(code), AddressingMode::Relative),
+                "{:02x}",
+                code
+            );
+        }
+        assert!(matches!(mode(0x6c), AddressingMode::Indirect));
+        assert!(matches!(mode(0x4c), AddressingMode::Absolute));
+        assert!(matches!(mode(0x20), AddressingMode::Absolute));
+    }
+
+    #[test]
+    fn test_jmp_indirect_operand() {
+        let jmp = Instruction::decode(&[0x6c, 0xff, 0x02], 0x8000).unwrap();
+        assert_eq!(jmp.operand(), "($02FF)");
+        assert_eq!(jmp.target(), None);
+    }
+
+    #[test]
+    fn test_unofficial_nop_mnemonic_alignment() {
+        let listing = disassemble(&[0x04, 0xa9, 0xea], 0xc6bd);
+
+        assert_eq!(
+            listing,
+            ["C6BD  04 A9    *NOP $A9", "C6BF  EA        NOP"].join("\n")
+        );
+    }
+}
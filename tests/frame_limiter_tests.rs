@@ -0,0 +1,34 @@
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use nerust_jg::frame_limiter::FrameLimiter;
+    use std::time::{Duration, Instant};
+
+    // ============================================================================
+    // Timing Tests
+    // ============================================================================
+
+    #[test]
+    fn test_wait_spaces_frames_at_target_rate() {
+        let mut limiter = FrameLimiter::new(60.0);
+        limiter.wait();
+        let start = Instant::now();
+        for _ in 0..6 {
+            limiter.wait();
+        }
+        let elapsed = start.elapsed();
+
+        // 6 frames of 16.7ms, with generous slack for a loaded test machine
+        assert!(elapsed >= Duration::from_millis(95), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(300), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_first_wait_returns_immediately() {
+        let mut limiter = FrameLimiter::new(1.0);
+        let start = Instant::now();
+        limiter.wait();
+
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+}
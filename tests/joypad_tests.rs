@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod test {
+    use nerust_jg::CPU;
+    use nerust_jg::Memory;
+    use nerust_jg::bus::Bus;
+    use nerust_jg::cartridge::RomBuilder;
+    use nerust_jg::cartridge::test::test_rom;
+    use nerust_jg::joypad::{Joypad, JoypadButton};
+    use std::ops::ControlFlow;
+
+    // ============================================================================
+    // Button Tests
+    // ============================================================================
+
+    #[test]
+    fn test_buttons_shift_out_in_order() {
+        let mut joypad = Joypad::new();
+        joypad.set_button_pressed_status(JoypadButton::BUTTON_A, true);
+        joypad.set_button_pressed_status(JoypadButton::START, true);
+        joypad.write(1);
+        joypad.write(0);
+
+        let bits: Vec<u8> = (0..8).map(|_| joypad.read()).collect();
+        assert_eq!(bits, vec![1, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(joypad.read(), 1); // Reads past the eighth return 1
+    }
+
+    #[test]
+    fn test_controller_reads_include_open_bus() {
+        let mut bus = Bus::new(test_rom()).unwrap();
+        bus.joypad1
+            .set_button_pressed_status(JoypadButton::BUTTON_A, true);
+        bus.mem_write(0x4016, 1);
+        bus.mem_write(0x4016, 0);
+
+        assert_eq!(bus.mem_read(0x4016), 0x41); // A pressed
+        assert_eq!(bus.mem_read(0x4016), 0x40); // B released
+        assert_eq!(bus.mem_read(0x4017), 0x40); // Controller 2, A released
+    }
+
+    // ============================================================================
+    // Turbo Tests
+    // ============================================================================
+
+    #[test]
+    fn test_turbo_button_alternates_with_frames() {
+        let mut joypad = Joypad::new();
+        joypad.set_button_pressed_status(JoypadButton::BUTTON_A, true);
+        joypad.set_turbo(JoypadButton::BUTTON_A, 15.0); // Two frames on, two off at 60fps
+        joypad.write(1); // Strobe held, so every read reports A
+
+        let mut presses = vec![];
+        for _ in 0..8 {
+            presses.push(joypad.read());
+            joypad.advance_frame();
+        }
+        assert_eq!(presses, vec![1, 1, 0, 0, 1, 1, 0, 0]);
+
+        joypad.clear_turbo(JoypadButton::BUTTON_A);
+        assert_eq!(joypad.read(), 1);
+    }
+
+    #[test]
+    fn test_turbo_button_released_stays_released() {
+        let mut joypad = Joypad::new();
+        joypad.set_turbo(JoypadButton::BUTTON_B, 30.0);
+        joypad.write(1);
+        joypad.write(0);
+
+        for _ in 0..4 {
+            joypad.advance_frame();
+            assert_eq!(joypad.peek(), 0); // A
+            joypad.read();
+            assert_eq!(joypad.peek(), 0); // B, turbo but not held
+            joypad.write(1);
+            joypad.write(0);
+        }
+    }
+
+    // ============================================================================
+    // Famicom Microphone Tests
+    // ============================================================================
+
+    #[test]
+    fn test_microphone_bit_in_famicom_mode() {
+        let mut bus = Bus::new(test_rom()).unwrap();
+        bus.joypad2.set_microphone(true);
+        assert_eq!(bus.mem_read(0x4016) & 0b100, 0); // NES controllers have no microphone
+
+        bus.joypad2.set_famicom_mode(true);
+        assert_eq!(bus.mem_read(0x4016) & 0b100, 0b100);
+        assert_eq!(bus.mem_read(0x4017) & 0b100, 0);
+
+        bus.joypad2.set_microphone(false);
+        assert_eq!(bus.mem_read(0x4016) & 0b100, 0);
+    }
+
+    // ============================================================================
+    // Input Injection Tests
+    // ============================================================================
+
+    #[test]
+    fn test_callback_presses_button_mid_run() {
+        let strobe_and_read = [
+            0xa9, 0x01, // LDA #$01
+            0x8d, 0x16, 0x40, // STA $4016
+            0xa9, 0x00, // LDA #$00
+            0x8d, 0x16, 0x40, // STA $4016
+            0xad, 0x16, 0x40, // LDA $4016
+        ];
+        let mut program = vec![];
+        program.extend(strobe_and_read);
+        program.extend([0x85, 0x10]); // STA $10
+        program.extend(strobe_and_read);
+        program.extend([0x85, 0x11]); // STA $11
+        program.extend([0xea, 0x02]); // NOP, KIL
+        let rom = RomBuilder::new().prg(program).reset_vector(0x8000).build();
+        let mut cpu = CPU::new(Bus::new(rom).unwrap());
+        cpu.reset();
+
+        cpu.run_with_mut_callback(|cpu| match cpu.program_counter {
+            0x800f => {
+                cpu.bus
+                    .joypad1
+                    .set_button_pressed_status(JoypadButton::BUTTON_A, true);
+                ControlFlow::Continue(())
+            }
+            0x801e => ControlFlow::Break(()), // Stop on the NOP
+            _ => ControlFlow::Continue(()),
+        });
+
+        assert_eq!(cpu.mem_read(0x10) & 1, 0);
+        assert_eq!(cpu.mem_read(0x11) & 1, 1);
+        assert_eq!(cpu.program_counter, 0x801e);
+        assert!(!cpu.halted);
+    }
+}
@@ -0,0 +1,321 @@
+#[cfg(test)]
+mod test {
+    use nerust_jg::Memory;
+    use nerust_jg::bus::Bus;
+    use nerust_jg::cartridge::{Rom, RomBuilder};
+    use nerust_jg::mapper::{BankWindow, Cnrom, Mapper, Mmc1, Mmc3, Nrom, Uxrom};
+    use nerust_jg::ppu::PPU;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // ============================================================================
+    // Helper Functions
+    // ============================================================================
+
+    /// Creates an iNES image with the given mapper number and PRG/CHR banks
+    fn create_rom(mapper: u8, prg_rom: Vec<u8>, chr_rom: Vec<u8>) -> Rom {
+        RomBuilder::new()
+            .mapper(mapper)
+            .prg(prg_rom)
+            .chr(chr_rom)
+            .build()
+    }
+
+    // ============================================================================
+    // NROM Tests
+    // ============================================================================
+
+    #[test]
+    fn test_nrom_single_bank_mirrors_full_range() {
+        let prg_rom: Vec<u8> = (0..16384).map(|i| (i * 7 + i / 256) as u8).collect();
+        let nrom = Nrom::new(&create_rom(0, prg_rom.clone(), vec![]));
+
+        for addr in 0x8000..=0xFFFFu16 {
+            let expected = prg_rom[(addr as usize - 0x8000) % 0x4000];
+            assert_eq!(nrom.cpu_read(addr), expected, "at {:04x}", addr);
+        }
+    }
+
+    #[test]
+    fn test_nrom_two_banks_are_not_mirrored() {
+        let mut prg_rom = vec![0x11; 16384];
+        prg_rom.extend(vec![0x22; 16384]);
+        let nrom = Nrom::new(&create_rom(0, prg_rom, vec![]));
+
+        assert_eq!(nrom.cpu_read(0x8000), 0x11);
+        assert_eq!(nrom.cpu_read(0xBFFF), 0x11);
+        assert_eq!(nrom.cpu_read(0xC000), 0x22);
+        assert_eq!(nrom.cpu_read(0xFFFF), 0x22);
+    }
+
+    #[test]
+    fn test_nrom_writes_are_ignored() {
+        let mut bus = Bus::new(create_rom(0, vec![0x5a; 16384], vec![])).unwrap();
+
+        bus.mem_write(0x8000, 0x12);
+        bus.mem_write(0xFFFF, 0x34);
+        assert_eq!(bus.mem_read(0x8000), 0x5a);
+        assert_eq!(bus.mem_read(0xC000), 0x5a);
+        assert_eq!(bus.mem_read(0xFFFF), 0x5a);
+    }
+
+    /// Writes `data` at PPU address `addr` through $2006/$2007
+    fn ppu_write(bus: &mut Bus, addr: u16, data: u8) {
+        bus.mem_write(0x2006, (addr >> 8) as u8);
+        bus.mem_write(0x2006, (addr & 0xFF) as u8);
+        bus.mem_write(0x2007, data);
+    }
+
+    fn ppu_read(bus: &mut Bus, addr: u16) -> u8 {
+        bus.mem_write(0x2006, (addr >> 8) as u8);
+        bus.mem_write(0x2006, (addr & 0xFF) as u8);
+        bus.mem_read(0x2007); // Dummy read fills the buffer
+        bus.mem_read(0x2007)
+    }
+
+    #[test]
+    fn test_nrom_chr_ram_accepts_writes() {
+        let mut bus = Bus::new(create_rom(0, vec![0; 16384], vec![])).unwrap();
+
+        ppu_write(&mut bus, 0x0010, 0xab);
+        ppu_write(&mut bus, 0x1fff, 0xcd);
+        assert_eq!(ppu_read(&mut bus, 0x0010), 0xab);
+        assert_eq!(ppu_read(&mut bus, 0x1fff), 0xcd);
+    }
+
+    #[test]
+    fn test_nrom_chr_rom_ignores_writes() {
+        let mut bus = Bus::new(create_rom(0, vec![0; 16384], vec![0x11; 8192])).unwrap();
+
+        ppu_write(&mut bus, 0x0010, 0xab);
+        assert_eq!(ppu_read(&mut bus, 0x0010), 0x11);
+    }
+
+    #[test]
+    fn test_nrom_snapshot_restores_chr_ram() {
+        let mut nrom = Nrom::new(&create_rom(0, vec![0; 16384], vec![]));
+        nrom.chr_write(0x0010, 0xab);
+        let state = nrom.snapshot();
+
+        nrom.chr_write(0x0010, 0xcd);
+        nrom.restore(&state);
+        assert_eq!(nrom.chr_read(0x0010), 0xab);
+    }
+
+    // ============================================================================
+    // UxROM Tests
+    // ============================================================================
+
+    /// Four 16KB banks, each filled with its own bank number
+    fn uxrom_prg() -> Vec<u8> {
+        (0..4u8).flat_map(|bank| vec![bank; 16384]).collect()
+    }
+
+    #[test]
+    fn test_uxrom_bank_switch() {
+        let mut uxrom = Uxrom::new(&create_rom(2, uxrom_prg(), vec![]));
+
+        assert_eq!(uxrom.cpu_read(0x8000), 0);
+        assert_eq!(uxrom.cpu_read(0xC000), 3); // Last bank is fixed
+        uxrom.cpu_write(0x8000, 2);
+        assert_eq!(uxrom.cpu_read(0xBFFF), 2);
+        assert_eq!(uxrom.cpu_read(0xFFFF), 3);
+    }
+
+    #[test]
+    fn test_uxrom_bus_conflict_ands_rom_byte() {
+        let mut prg_rom = uxrom_prg();
+        prg_rom[3 * 16384 + 0x0005] = 0x01; // Byte at $C005 in the fixed bank
+        let mut bus = Bus::new(create_rom(2, prg_rom, vec![])).unwrap();
+
+        bus.mem_write(0xC005, 0x03); // 0x03 & 0x01 selects bank 1
+        assert_eq!(bus.mem_read(0x8000), 1);
+
+        bus.mem_write(0xC000, 0x02); // 0x02 & 0x03 selects bank 2
+        assert_eq!(bus.mem_read(0x8000), 2);
+    }
+
+    #[test]
+    fn test_uxrom_current_banks() {
+        let mut uxrom = Uxrom::new(&create_rom(2, uxrom_prg(), vec![]));
+        uxrom.cpu_write(0x8000, 1);
+
+        let banks = uxrom.current_banks();
+        assert_eq!(banks.prg[0].bank, 1);
+        assert_eq!(banks.prg[1].bank, 3);
+    }
+
+    #[test]
+    fn test_uxrom_snapshot_restores_bank() {
+        let mut uxrom = Uxrom::new(&create_rom(2, uxrom_prg(), vec![]));
+        uxrom.cpu_write(0x8000, 2);
+        let state = uxrom.snapshot();
+
+        uxrom.cpu_write(0x8000, 0);
+        uxrom.restore(&state);
+        assert_eq!(uxrom.cpu_read(0x8000), 2);
+    }
+
+    // ============================================================================
+    // CNROM Tests
+    // ============================================================================
+
+    #[test]
+    fn test_cnrom_chr_bank_switch() {
+        let chr_rom: Vec<u8> = (0..4u8).flat_map(|bank| vec![bank; 8192]).collect();
+        let mut cnrom = Cnrom::new(&create_rom(3, vec![0xFF; 16384], chr_rom));
+
+        assert_eq!(cnrom.chr_read(0x0000), 0);
+        cnrom.cpu_write(0x8000, 2);
+        assert_eq!(cnrom.chr_read(0x1FFF), 2);
+        assert_eq!(cnrom.current_banks().chr[0].bank, 2);
+    }
+
+    // ============================================================================
+    // MMC1 Tests
+    // ============================================================================
+
+    /// Loads `value` into the MMC1 register at `addr` through the serial port, LSB first
+    fn mmc1_write(mmc1: &mut Mmc1, addr: u16, value: u8) {
+        for bit in 0..5 {
+            mmc1.cpu_write(addr, (value >> bit) & 1);
+        }
+    }
+
+    #[test]
+    fn test_mmc1_power_on_fixes_last_bank() {
+        let mmc1 = Mmc1::new(&create_rom(1, uxrom_prg(), vec![]));
+
+        assert_eq!(mmc1.cpu_read(0x8000), 0);
+        assert_eq!(mmc1.cpu_read(0xC000), 3);
+    }
+
+    #[test]
+    fn test_mmc1_prg_bank_switch_reports_current_banks() {
+        let mut mmc1 = Mmc1::new(&create_rom(1, uxrom_prg(), vec![]));
+        mmc1_write(&mut mmc1, 0xE000, 2);
+
+        assert_eq!(mmc1.cpu_read(0x8000), 2);
+        assert_eq!(
+            mmc1.current_banks().prg[0],
+            BankWindow {
+                start: 0x8000,
+                size: 16384,
+                bank: 2,
+            }
+        );
+        assert_eq!(mmc1.current_banks().prg[1].bank, 3);
+    }
+
+    #[test]
+    fn test_mmc1_reset_bit_clears_partial_write() {
+        let mut mmc1 = Mmc1::new(&create_rom(1, uxrom_prg(), vec![]));
+        mmc1.cpu_write(0xE000, 1);
+        mmc1.cpu_write(0xE000, 0x80); // Drops the bit shifted in so far
+        mmc1_write(&mut mmc1, 0xE000, 1);
+
+        assert_eq!(mmc1.cpu_read(0x8000), 1);
+    }
+
+    #[test]
+    fn test_mmc1_snapshot_restores_partial_write() {
+        let mut mmc1 = Mmc1::new(&create_rom(1, uxrom_prg(), vec![]));
+        mmc1_write(&mut mmc1, 0xE000, 2);
+        mmc1.cpu_write(0xE000, 1); // First bit of the next value is in the shift register
+        let state = mmc1.snapshot();
+
+        mmc1_write(&mut mmc1, 0xE000, 0);
+        mmc1.restore(&state);
+        assert_eq!(mmc1.cpu_read(0x8000), 2);
+        for _ in 1..5 {
+            mmc1.cpu_write(0xE000, 0); // Completes the value 1
+        }
+        assert_eq!(mmc1.cpu_read(0x8000), 1);
+    }
+
+    #[test]
+    fn test_mmc1_4kb_chr_banks() {
+        let chr_rom: Vec<u8> = (0..4u8).flat_map(|bank| vec![bank; 4096]).collect();
+        let mut mmc1 = Mmc1::new(&create_rom(1, uxrom_prg(), chr_rom));
+        mmc1_write(&mut mmc1, 0x8000, 0b1_1100); // 4KB CHR mode
+        mmc1_write(&mut mmc1, 0xA000, 3);
+        mmc1_write(&mut mmc1, 0xC000, 1);
+
+        assert_eq!(mmc1.chr_read(0x0000), 3);
+        assert_eq!(mmc1.chr_read(0x1000), 1);
+        let chr_banks: Vec<usize> = mmc1.current_banks().chr.iter().map(|w| w.bank).collect();
+        assert_eq!(chr_banks, vec![3, 1]);
+    }
+
+    // ============================================================================
+    // MMC3 Tests
+    // ============================================================================
+
+    const SHOW_BACKGROUND: u8 = 0b0000_1000;
+    const SHOW_SPRITES: u8 = 0b0001_0000;
+
+    /// MMC3 with the IRQ armed for `latch`, behind a PPU fetching sprites from $1000 and
+    /// sitting at the start of the pre-render line
+    fn mmc3_ppu(latch: u8, mask: u8) -> (Rc<RefCell<Mmc3>>, PPU) {
+        let mmc3 = Rc::new(RefCell::new(Mmc3::new(&create_rom(
+            4,
+            vec![0; 32768],
+            vec![0; 8192],
+        ))));
+        let mut ppu = PPU::new(mmc3.clone());
+        ppu.write_to_control_reg(0b0000_1000); // Sprites at $1000, background at $0000
+        ppu.tick(261 * 341);
+
+        mmc3.borrow_mut().cpu_write(0xC000, latch);
+        mmc3.borrow_mut().cpu_write(0xC001, 0);
+        mmc3.borrow_mut().cpu_write(0xE001, 0);
+        ppu.write_to_mask(mask);
+        (mmc3, ppu)
+    }
+
+    /// Ticks one dot at a time until the IRQ line goes low, returning (scanline, dot)
+    fn run_until_irq(mmc3: &Rc<RefCell<Mmc3>>, ppu: &mut PPU) -> Option<(u16, u16)> {
+        for _ in 0..262 * 341 {
+            ppu.tick(1);
+            if mmc3.borrow().irq_pending() {
+                return Some((ppu.scanline(), ppu.dot()));
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_mmc3_irq_scanline_with_full_rendering() {
+        let (mmc3, mut ppu) = mmc3_ppu(10, SHOW_BACKGROUND | SHOW_SPRITES);
+
+        // Reloaded on the pre-render line, then counted down once per visible line
+        let (scanline, dot) = run_until_irq(&mmc3, &mut ppu).unwrap();
+        assert_eq!(scanline, 9);
+        assert!((257..=320).contains(&dot), "dot {}", dot);
+    }
+
+    #[test]
+    fn test_mmc3_irq_scanline_with_sprites_only_rendering() {
+        let (mmc3, mut ppu) = mmc3_ppu(10, SHOW_SPRITES);
+        let sprites_only = run_until_irq(&mmc3, &mut ppu);
+
+        let (mmc3, mut ppu) = mmc3_ppu(10, SHOW_BACKGROUND | SHOW_SPRITES);
+        assert_eq!(sprites_only, run_until_irq(&mmc3, &mut ppu));
+        assert_eq!(sprites_only.map(|(scanline, _)| scanline), Some(9));
+    }
+
+    #[test]
+    fn test_mmc3_irq_needs_rendering() {
+        let (mmc3, mut ppu) = mmc3_ppu(10, 0);
+        assert_eq!(run_until_irq(&mmc3, &mut ppu), None);
+    }
+
+    #[test]
+    fn test_mmc3_irq_disable_acknowledges() {
+        let (mmc3, mut ppu) = mmc3_ppu(1, SHOW_SPRITES);
+        assert!(run_until_irq(&mmc3, &mut ppu).is_some());
+
+        mmc3.borrow_mut().cpu_write(0xE000, 0);
+        assert!(!mmc3.borrow().irq_pending());
+    }
+}
@@ -0,0 +1,325 @@
+#[cfg(test)]
+mod test {
+    use nerust_jg::Memory;
+    use nerust_jg::apu::Apu;
+    use nerust_jg::cartridge::{Mirroring, Rom, RomBuilder};
+    use nerust_jg::cpu::AddressingMode;
+    use nerust_jg::nes::{AccuracyReport, Nes};
+    use nerust_jg::render::palette::SYSTEM_PALLETE;
+
+    // ============================================================================
+    // Helper Functions
+    // ============================================================================
+
+    /// NROM image whose CHR tile 1 is solid colour 1 and tile 2 is solid colour 3
+    fn create_tile_rom() -> Rom {
+        let mut chr_rom = vec![0; 8192];
+        chr_rom[16..24].fill(0xFF);
+        chr_rom[32..48].fill(0xFF);
+        RomBuilder::new().chr(chr_rom).build()
+    }
+
+    fn ppu_write(nes: &mut Nes, addr: u16, data: &[u8]) {
+        nes.cpu.bus.mem_write(0x2006, (addr >> 8) as u8);
+        nes.cpu.bus.mem_write(0x2006, (addr & 0xFF) as u8);
+        for &value in data {
+            nes.cpu.bus.mem_write(0x2007, value);
+        }
+    }
+
+    // $2007 reads lag one behind, so the first read only fills the buffer
+    fn ppu_read(nes: &mut Nes, addr: u16) -> u8 {
+        nes.cpu.bus.mem_write(0x2006, (addr >> 8) as u8);
+        nes.cpu.bus.mem_write(0x2006, (addr & 0xFF) as u8);
+        nes.cpu.bus.mem_read(0x2007);
+        nes.cpu.bus.mem_read(0x2007)
+    }
+
+    /// Nametable 0 with tile 1 at (0, 0) and tile 2 at (31, 29) using palette 3
+    fn create_nes_with_nametable() -> Nes {
+        let mut nes = Nes::new(create_tile_rom()).unwrap();
+        ppu_write(&mut nes, 0x2000, &[1]);
+        ppu_write(&mut nes, 0x2000 + 29 * 32 + 31, &[2]);
+        ppu_write(&mut nes, 0x23C0 + 7 * 8 + 7, &[0b11 << 2]); // Top-right quadrant
+        ppu_write(&mut nes, 0x3F00, &[0x0F, 0x30]);
+        ppu_write(&mut nes, 0x3F0D, &[0x11, 0x12, 0x16]);
+        nes.cpu.bus.mem_write(0x2001, 0b0000_1010); // Background on, left column shown
+        nes
+    }
+
+    // ============================================================================
+    // Nametable Rendering Tests
+    // ============================================================================
+
+    #[test]
+    fn test_render_nametable_draws_full_table() {
+        let nes = create_nes_with_nametable();
+        let frame = nes.render_nametable(0);
+
+        assert_eq!(frame.get_pixel(0, 0), SYSTEM_PALLETE[0x30]);
+        assert_eq!(frame.get_pixel(7, 7), SYSTEM_PALLETE[0x30]);
+        assert_eq!(frame.get_pixel(8, 0), SYSTEM_PALLETE[0x0F]);
+        assert_eq!(frame.get_pixel(255, 239), SYSTEM_PALLETE[0x16]);
+    }
+
+    #[test]
+    fn test_left_column_clipped_to_backdrop() {
+        let mut nes = create_nes_with_nametable();
+        nes.cpu.bus.mem_write(0x2001, 0b0000_1000); // Background on, left column hidden
+        let frame = nes.step_frame();
+
+        for (x, y) in [(0, 0), (7, 0), (0, 7), (7, 7), (3, 239)] {
+            assert_eq!(frame.get_pixel(x, y), SYSTEM_PALLETE[0x0F]);
+        }
+        assert_eq!(frame.get_pixel(255, 239), SYSTEM_PALLETE[0x16]);
+        assert_eq!(
+            nes.render_nametable(0).get_pixel(0, 0),
+            SYSTEM_PALLETE[0x30]
+        ); // Debug view is unclipped
+    }
+
+    #[test]
+    fn test_mask_enables_background_and_sprites() {
+        let mut nes = create_nes_with_nametable();
+        nes.cpu.bus.mem_write(0x2001, 0x1E);
+        assert!(nes.cpu.bus.ppu().is_background_enabled());
+        assert!(nes.cpu.bus.ppu().is_sprites_enabled());
+
+        nes.cpu.bus.mem_write(0x2001, 0b0001_0110); // Sprites only
+        assert!(!nes.cpu.bus.ppu().is_background_enabled());
+        let frame = nes.step_frame();
+        for (x, y) in [(0, 0), (100, 100), (255, 239)] {
+            assert_eq!(frame.get_pixel(x, y), SYSTEM_PALLETE[0x0F]);
+        }
+    }
+
+    #[test]
+    fn test_frame_buffer_matches_step_frame() {
+        let mut nes = create_nes_with_nametable();
+        let frame = nes.step_frame();
+
+        assert_eq!(nes.frame_buffer().len(), 256 * 240 * 3);
+        assert_eq!(nes.frame_buffer(), &frame.data[..]);
+
+        nes.run_frame();
+        let (r, g, b) = SYSTEM_PALLETE[0x30];
+        assert_eq!(&nes.frame_buffer()[0..3], &[r, g, b]);
+    }
+
+    #[cfg(feature = "image")]
+    fn read_png_pixel(png: &[u8], x: usize, y: usize) -> (u8, u8, u8) {
+        // Gather the IDAT payload, then walk the stored deflate blocks
+        let mut offset = 8;
+        let mut zlib = vec![];
+        while offset < png.len() {
+            let len = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+            if &png[offset + 4..offset + 8] == b"IDAT" {
+                zlib.extend_from_slice(&png[offset + 8..offset + 8 + len]);
+            }
+            offset += len + 12;
+        }
+
+        let mut raw = vec![];
+        let mut offset = 2;
+        loop {
+            let last = zlib[offset] & 1 == 1;
+            let len = u16::from_le_bytes([zlib[offset + 1], zlib[offset + 2]]) as usize;
+            raw.extend_from_slice(&zlib[offset + 5..offset + 5 + len]);
+            offset += 5 + len;
+            if last {
+                break;
+            }
+        }
+
+        let base = y * (256 * 3 + 1) + 1 + x * 3;
+        (raw[base], raw[base + 1], raw[base + 2])
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_export_nametable_png() {
+        let nes = create_nes_with_nametable();
+        let path = std::env::temp_dir().join("nerust_jg_nametable_test.png");
+        nes.export_nametable_png(0, &path).unwrap();
+
+        let png = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 1, 0, 0, 0, 0, 240]);
+        assert_eq!(read_png_pixel(&png, 3, 4), SYSTEM_PALLETE[0x30]);
+        assert_eq!(read_png_pixel(&png, 100, 100), SYSTEM_PALLETE[0x0F]);
+        assert_eq!(read_png_pixel(&png, 250, 235), SYSTEM_PALLETE[0x16]);
+    }
+
+    #[test]
+    fn test_override_mirroring_moves_nametable_readback() {
+        let vertical = || RomBuilder::new().mirroring(Mirroring::Vertical).build();
+
+        let mut nes = Nes::new(vertical()).unwrap();
+        ppu_write(&mut nes, 0x2000, &[0xAB]);
+        assert_eq!(ppu_read(&mut nes, 0x2800), 0xAB);
+        assert_eq!(ppu_read(&mut nes, 0x2400), 0x00);
+
+        let mut nes = Nes::new(vertical()).unwrap();
+        nes.override_mirroring(Mirroring::Horizontal);
+        ppu_write(&mut nes, 0x2000, &[0xAB]);
+        assert_eq!(ppu_read(&mut nes, 0x2400), 0xAB);
+        assert_eq!(ppu_read(&mut nes, 0x2800), 0x00);
+
+        let mut rom = vertical();
+        rom.set_mirroring(Mirroring::Horizontal);
+        let mut nes = Nes::new(rom).unwrap();
+        ppu_write(&mut nes, 0x2000, &[0xAB]);
+        assert_eq!(ppu_read(&mut nes, 0x2400), 0xAB);
+    }
+
+    // ============================================================================
+    // Audio/Video Sync Tests
+    // ============================================================================
+
+    #[test]
+    fn test_cycles_for_host_frame_follows_audio_fill() {
+        let nes = Nes::new(create_tile_rom()).unwrap();
+        let balanced = nes.cycles_for_host_frame(60.0, 2048, 4096);
+        let low = nes.cycles_for_host_frame(60.0, 256, 4096);
+        let full = nes.cycles_for_host_frame(60.0, 4096, 4096);
+
+        assert_eq!(balanced, 29830); // 1789773 Hz / 60 Hz
+        assert!(low > balanced);
+        assert!(full < balanced);
+        assert!(full > balanced * 99 / 100);
+    }
+
+    #[test]
+    fn test_step_frame_audio_does_not_drift() {
+        let rom = RomBuilder::new()
+            .prg(vec![0x4c, 0x00, 0x80]) // JMP $8000
+            .reset_vector(0x8000)
+            .build();
+        let mut nes = Nes::new(rom).unwrap();
+
+        let mut total = 0;
+        for _ in 0..120 {
+            nes.step_frame();
+            let samples = nes.audio_samples().len();
+            assert!((733..=734).contains(&samples), "{} samples", samples); // 29780.67 cycles
+            total += samples;
+        }
+
+        // Same as rendering the 120 frames' cycles in one go
+        let cycles = 120 * 262 * 341 / 3;
+        assert_eq!(total, Apu::new(44_100).render_samples(cycles).len());
+        assert!(nes.cpu.cycles >= cycles);
+    }
+
+    #[test]
+    fn test_apu_write_only_changes_later_samples() {
+        let rom = RomBuilder::new()
+            .prg(vec![
+                0xa2, 0xff, // LDX #$FF
+                0xca, // DEX
+                0xd0, 0xfd, // BNE -3, about 1280 cycles of silence
+                0xa9, 0x01, 0x8d, 0x15, 0x40, // Enable pulse 1
+                0xa9, 0xbf, 0x8d, 0x00, 0x40, // 50% duty, constant volume 15
+                0xa9, 0xfd, 0x8d, 0x02, 0x40, // Timer low
+                0xa9, 0x08, 0x8d, 0x03, 0x40, // Timer high, load the length counter
+                0x4c, 0x19, 0x80, // JMP $8019
+            ])
+            .reset_vector(0x8000)
+            .build();
+        let mut nes = Nes::new(rom).unwrap();
+
+        nes.run_frame();
+        let samples = nes.audio_samples();
+        assert!(samples[..30].iter().all(|&sample| sample == 0.0));
+        assert!(samples[30..].iter().any(|&sample| sample > 0.0));
+    }
+
+    // ============================================================================
+    // Accuracy Report Tests
+    // ============================================================================
+
+    #[test]
+    fn test_accuracy_report_matches_known_fixture() {
+        let expected = AccuracyReport {
+            open_bus: false,
+            controller_open_bus: true,
+            jmp_indirect_page_wrap: true,
+            page_cross_cycles: true,
+            rmw_dummy_write: true,
+            unstable_opcodes: true,
+            vblank_nmi: true,
+            sprite_overflow: true,
+            sprite_zero_hit: false,
+            ppu_warm_up: false,
+            odd_frame_skip: false,
+            bus_conflicts: true,
+        };
+        assert_eq!(Nes::accuracy_report(), expected);
+    }
+
+    #[test]
+    fn test_accuracy_report_claims_match_behaviour() {
+        let report = Nes::accuracy_report();
+        let mut nes = Nes::new(RomBuilder::new().build()).unwrap();
+
+        let controller = nes.cpu.bus.mem_read(0x4016);
+        assert_eq!(report.controller_open_bus, controller & 0xe0 == 0x40);
+
+        nes.cpu.mem_write_u16(0x0010, 0x02ff); // JMP ($02FF) operand
+        nes.cpu.mem_write(0x02ff, 0x00);
+        nes.cpu.mem_write(0x0200, 0x03); // High byte without the page carry
+        nes.cpu.mem_write(0x0300, 0x04); // High byte with it
+        nes.cpu.program_counter = 0x0010;
+        let target = nes.cpu.get_operand_address(&AddressingMode::Indirect);
+        assert_eq!(report.jmp_indirect_page_wrap, target == 0x0300);
+    }
+
+    // ============================================================================
+    // Save State Tests
+    // ============================================================================
+
+    /// `INC $10`, `JMP $8000`: RAM changes every few cycles
+    fn create_counter_nes() -> Nes {
+        let rom = RomBuilder::new()
+            .prg(vec![0xe6, 0x10, 0x4c, 0x00, 0x80])
+            .reset_vector(0x8000)
+            .build();
+        Nes::new(rom).unwrap()
+    }
+
+    #[test]
+    fn test_load_state_replays_the_same_frame() {
+        let mut nes = create_counter_nes();
+        nes.run_frame();
+        let state = nes.save_state();
+        nes.run_frame();
+        let (counter, cycles) = (nes.cpu.mem_read(0x10), nes.cpu.cycles);
+
+        nes.run_frame();
+        nes.load_state(&state);
+        assert_eq!(nes.frame_count(), 1);
+        nes.run_frame();
+        assert_eq!(nes.cpu.mem_read(0x10), counter);
+        assert_eq!(nes.cpu.cycles, cycles);
+    }
+
+    #[test]
+    fn test_state_slot_round_trip() {
+        let mut nes = create_counter_nes();
+        for _ in 0..3 {
+            nes.run_frame();
+        }
+        nes.save_state_slot(1);
+        assert_eq!(nes.save_slot(1).map(|slot| slot.frames), Some(3));
+
+        for _ in 0..2 {
+            nes.run_frame();
+        }
+        assert!(nes.load_state_slot(1));
+        assert_eq!(nes.frame_count(), 3);
+        assert!(!nes.load_state_slot(2)); // Never saved
+        assert_eq!(nes.frame_count(), 3);
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod test {
+    use nerust_jg::CPU;
+    use nerust_jg::Memory;
+    use nerust_jg::cpu::{CpuError, FlatMemory};
+    use nerust_jg::opcodes::{self, OPCODES_MAP};
+
+    // ============================================================================
+    // Helper Functions
+    // ============================================================================
+
+    /// Executes `code` once over zeroed memory, the following BRK vectors to a KIL that
+    /// ends the run
+    fn executes(code: u8) -> bool {
+        let mut memory = FlatMemory::new();
+        memory.mem_write(0x0700, 0x02);
+        memory.mem_write_u16(0xfffe, 0x0700);
+        memory.mem_write(0x0600, code);
+        let mut cpu = CPU::new(memory);
+        cpu.program_counter = 0x0600;
+        cpu.run();
+        !matches!(cpu.error(), Some(CpuError::UnimplementedOpcode { .. }))
+    }
+
+    // ============================================================================
+    // Implemented Opcode Tests
+    // ============================================================================
+
+    #[test]
+    fn test_is_implemented() {
+        assert!(opcodes::is_implemented(0xa9)); // LDA #imm
+        assert!(!opcodes::is_implemented(0x12)); // Not in the opcode table
+        assert!(opcodes::implemented().contains(&0xa9));
+        assert!(!opcodes::implemented().contains(&0x12));
+        assert!(opcodes::is_implemented(0x70)); // BVS, the last official opcode added
+    }
+
+    #[test]
+    fn test_unstable_opcodes_are_marked() {
+        for code in [0x9c, 0x9e, 0x9f, 0x93, 0x9b, 0xbb, 0x8b, 0xab] {
+            let opcode = OPCODES_MAP[&code];
+            assert!(
+                opcode.is_unofficial() && opcode.is_unstable(),
+                "{:02x}",
+                code
+            );
+        }
+        assert!(!OPCODES_MAP[&0xa7].is_unstable()); // *LAX zero page is stable
+    }
+
+    #[test]
+    fn test_implemented_matches_run_dispatch() {
+        for &code in OPCODES_MAP.keys() {
+            assert_eq!(
+                opcodes::is_implemented(code),
+                executes(code),
+                "opcode {:02x}",
+                code
+            );
+        }
+    }
+}
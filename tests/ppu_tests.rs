@@ -0,0 +1,382 @@
+#[cfg(test)]
+mod test {
+    use nerust_jg::cartridge::{Mirroring, Rom};
+    use nerust_jg::mapper::{self, Mapper};
+    use nerust_jg::ppu::{
+        AddressRegister, OAM_SIZE, POWER_ON_STATUS, PPU, PpuRegistersSnapshot, SCREEN_HEIGHT,
+        SCREEN_WIDTH, SPRITE_COUNT,
+    };
+    use nerust_jg::render::frame::Frame;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // ============================================================================
+    // Helper Functions
+    // ============================================================================
+
+    /// Mapper with its own RAM standing in for nametable 2 ($2800-$2BFF)
+    struct NametableRamMapper {
+        ram: [u8; 0x400],
+    }
+
+    impl Mapper for NametableRamMapper {
+        fn cpu_read(&self, _addr: u16) -> u8 {
+            0
+        }
+
+        fn cpu_write(&mut self, _addr: u16, _data: u8) {}
+
+        fn chr_read(&self, _addr: u16) -> u8 {
+            0
+        }
+
+        fn mirroring(&self) -> Mirroring {
+            Mirroring::Horizontal
+        }
+
+        fn nt_read(&self, addr: u16) -> Option<u8> {
+            match addr {
+                0x2800..=0x2BFF => Some(self.ram[(addr - 0x2800) as usize]),
+                _ => None,
+            }
+        }
+
+        fn nt_write(&mut self, addr: u16, data: u8) -> bool {
+            match addr {
+                0x2800..=0x2BFF => {
+                    self.ram[(addr - 0x2800) as usize] = data;
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+
+    fn test_mapper() -> Rc<RefCell<NametableRamMapper>> {
+        Rc::new(RefCell::new(NametableRamMapper { ram: [0; 0x400] }))
+    }
+
+    fn set_ppu_addr(ppu: &mut PPU, addr: u16) {
+        ppu.write_to_addr_reg((addr >> 8) as u8);
+        ppu.write_to_addr_reg((addr & 0xFF) as u8);
+    }
+
+    // ============================================================================
+    // Address Register Tests
+    // ============================================================================
+
+    #[test]
+    fn test_addr_reg_transfers_on_second_write() {
+        let mut addr_reg = AddressRegister::new();
+
+        addr_reg.update(0x3F);
+        assert_eq!(addr_reg.temp(), 0x3F00);
+        assert_eq!(addr_reg.get(), 0x0000); // v is untouched by the first write
+
+        addr_reg.update(0x00);
+        assert_eq!(addr_reg.temp(), 0x3F00);
+        assert_eq!(addr_reg.get(), 0x3F00);
+    }
+
+    #[test]
+    fn test_addr_reg_masks_high_byte() {
+        let mut addr_reg = AddressRegister::new();
+
+        addr_reg.update(0xFF); // Bits 6 and 7 are dropped
+        addr_reg.update(0x12);
+
+        assert_eq!(addr_reg.temp(), 0x3F12);
+        assert_eq!(addr_reg.get(), 0x3F12);
+    }
+
+    // ============================================================================
+    // Data Register Tests
+    // ============================================================================
+
+    #[test]
+    fn test_data_write_leaves_read_buffer_alone() {
+        let mut ppu = PPU::new(test_mapper());
+        set_ppu_addr(&mut ppu, 0x2000);
+        ppu.write_to_data_reg(0x11);
+        ppu.write_to_data_reg(0x22);
+
+        set_ppu_addr(&mut ppu, 0x2000);
+        assert_eq!(ppu.read_data(), 0x00); // Stale buffer from power-on
+        assert_eq!(ppu.read_data(), 0x11); // Buffer now holds $2001
+
+        ppu.write_to_data_reg(0x33); // Lands at $2002, the buffer is untouched
+        assert_eq!(ppu.read_data(), 0x22);
+        assert_eq!(ppu.vram[ppu.mirror_vram_addr(0x2002) as usize], 0x33);
+    }
+
+    // ============================================================================
+    // Mapper Nametable Tests
+    // ============================================================================
+
+    #[test]
+    fn test_mapper_nametable_read() {
+        let mut mapper = NametableRamMapper { ram: [0; 0x400] };
+        mapper.ram[0x05] = 0x66;
+        let mut ppu = PPU::new(Rc::new(RefCell::new(mapper)));
+
+        set_ppu_addr(&mut ppu, 0x2805);
+        ppu.read_data(); // Fills the read buffer
+        assert_eq!(ppu.read_data(), 0x66);
+    }
+
+    #[test]
+    fn test_mapper_nametable_write_bypasses_vram() {
+        let mapper = Rc::new(RefCell::new(NametableRamMapper { ram: [0; 0x400] }));
+        let mut ppu = PPU::new(mapper.clone());
+
+        set_ppu_addr(&mut ppu, 0x2810);
+        ppu.write_to_data_reg(0x77);
+        set_ppu_addr(&mut ppu, 0x2010);
+        ppu.write_to_data_reg(0x55); // Nametable 0 is declined by the mapper
+
+        assert_eq!(mapper.borrow().ram[0x10], 0x77);
+        assert_eq!(ppu.vram[0x010], 0x55);
+        assert!(ppu.vram[0x400..].iter().all(|&b| b == 0));
+    }
+
+    // ============================================================================
+    // Register Snapshot Tests
+    // ============================================================================
+
+    #[test]
+    fn test_registers_snapshot() {
+        let mut ppu = PPU::new(test_mapper());
+        ppu.write_to_control_reg(0b1000_0110); // NMI, +32 increment, nametable 2
+        ppu.write_to_mask(0b0001_1110);
+        ppu.write_to_scroll(0x7D); // Coarse X 15, fine X 5
+        ppu.write_to_scroll(0x5E); // Coarse Y 11, fine Y 6
+        ppu.write_to_oam_addr(0x10);
+
+        let snapshot = ppu.registers_snapshot();
+
+        assert_eq!(snapshot.control, 0b1000_0110);
+        assert_eq!(snapshot.mask, 0b0001_1110);
+        assert_eq!(snapshot.status, POWER_ON_STATUS);
+        assert_eq!(snapshot.oam_addr, 0x10);
+        assert_eq!(snapshot.scroll_x, 0x7D);
+        assert_eq!(snapshot.scroll_y, 0x5E);
+        assert_eq!(snapshot.fine_x, 5);
+        assert_eq!(snapshot.vram_addr, 0x0000); // v is only loaded by $2006
+        assert_eq!(snapshot.temp_vram_addr, 0b110_1001_0110_1111); // fine Y 6, nametable 2, coarse Y 11, coarse X 15
+    }
+
+    #[test]
+    fn test_power_on_registers() {
+        let ppu = PPU::new(test_mapper());
+
+        assert_eq!(
+            ppu.registers_snapshot(),
+            PpuRegistersSnapshot {
+                control: 0,
+                mask: 0,
+                status: 0b1010_0000, // Vblank and sprite overflow
+                oam_addr: 0,
+                scroll_x: 0,
+                scroll_y: 0,
+                vram_addr: 0,
+                temp_vram_addr: 0,
+                fine_x: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_status_clears_vblank() {
+        let mut ppu = PPU::new(test_mapper());
+
+        assert_eq!(ppu.read_status(), POWER_ON_STATUS);
+        assert_eq!(ppu.read_status(), 0b0010_0000); // Sprite overflow stays
+    }
+
+    #[test]
+    fn test_reset_keeps_status_oam_addr_and_vram_addr() {
+        let mut ppu = PPU::new(test_mapper());
+        ppu.write_to_control_reg(0b1000_0000);
+        ppu.write_to_mask(0b0001_1110);
+        ppu.write_to_oam_addr(0x20);
+        set_ppu_addr(&mut ppu, 0x2108);
+        ppu.write_to_scroll(0x7D); // First write, leaves the latch open
+
+        ppu.reset();
+        let snapshot = ppu.registers_snapshot();
+
+        assert_eq!(snapshot.control, 0);
+        assert_eq!(snapshot.mask, 0);
+        assert_eq!(snapshot.status, POWER_ON_STATUS);
+        assert_eq!(snapshot.oam_addr, 0x20);
+        assert_eq!(snapshot.scroll_x, 0);
+        assert_eq!(snapshot.vram_addr, 0x2108);
+
+        // The latch was cleared, so this is a first write again
+        ppu.write_to_addr_reg(0x3F);
+        ppu.write_to_addr_reg(0x00);
+        assert_eq!(ppu.registers_snapshot().vram_addr, 0x3F00);
+    }
+
+    // ============================================================================
+    // VRAM Diff Tests
+    // ============================================================================
+
+    #[test]
+    fn test_vram_diff_lists_changed_cells() {
+        let mut ppu = PPU::new(test_mapper());
+        set_ppu_addr(&mut ppu, 0x2005);
+        ppu.write_to_data_reg(0x11);
+        set_ppu_addr(&mut ppu, 0x23FF);
+        ppu.write_to_data_reg(0x22);
+        let previous = ppu.vram;
+
+        set_ppu_addr(&mut ppu, 0x2005);
+        ppu.write_to_data_reg(0x33);
+        ppu.write_to_data_reg(0x00); // $2006 stays the same value
+        set_ppu_addr(&mut ppu, 0x2C10); // Horizontal mirroring, second table
+        ppu.write_to_data_reg(0x44);
+
+        assert_eq!(
+            ppu.vram_diff(&previous),
+            vec![(0x2005, 0x11, 0x33), (0x2810, 0x00, 0x44)]
+        );
+    }
+
+    // ============================================================================
+    // Sprite Evaluation Tests
+    // ============================================================================
+
+    const STATUS_SPRITE_OVERFLOW: u8 = 0b0010_0000;
+
+    /// PPU with `count` sprites on scanline 0, sprites shown, at dot 0 of scanline 0 with
+    /// the power-on overflow flag cleared by the pre-render line
+    fn create_ppu_with_sprites_on_line_0(count: usize) -> PPU {
+        let mut ppu = PPU::new(test_mapper());
+        ppu.oam.fill(0xFF); // Y of $FF is below the screen
+        for sprite in 0..count {
+            ppu.oam[sprite * 4] = 0;
+        }
+        ppu.write_to_mask(0b0001_0000);
+        ppu.tick(262 * 341);
+        ppu
+    }
+
+    #[test]
+    fn test_sprite_overflow_set_when_ninth_sprite_is_read() {
+        let mut ppu = create_ppu_with_sprites_on_line_0(9);
+        assert_eq!(ppu.registers_snapshot().status & STATUS_SPRITE_OVERFLOW, 0);
+
+        // Sprites 0-7 take 8 dots each from dot 65, so sprite 8 is read on dot 129
+        ppu.tick(129);
+        assert_eq!((ppu.scanline(), ppu.dot()), (0, 129));
+        assert_eq!(ppu.registers_snapshot().status & STATUS_SPRITE_OVERFLOW, 0);
+
+        ppu.tick(1);
+        assert_ne!(ppu.registers_snapshot().status & STATUS_SPRITE_OVERFLOW, 0);
+    }
+
+    #[test]
+    fn test_eight_sprites_do_not_overflow() {
+        let mut ppu = create_ppu_with_sprites_on_line_0(8);
+        ppu.tick(341);
+
+        assert_eq!(ppu.registers_snapshot().status & STATUS_SPRITE_OVERFLOW, 0);
+    }
+
+    #[test]
+    fn test_sprite_overflow_cleared_on_pre_render_line() {
+        let mut ppu = create_ppu_with_sprites_on_line_0(9);
+        ppu.tick(341);
+        assert_ne!(ppu.registers_snapshot().status & STATUS_SPRITE_OVERFLOW, 0);
+
+        ppu.tick(260 * 341 + 1); // Up to dot 1 of the pre-render line
+        assert_ne!(ppu.registers_snapshot().status & STATUS_SPRITE_OVERFLOW, 0);
+        ppu.tick(1);
+        assert_eq!(ppu.registers_snapshot().status & STATUS_SPRITE_OVERFLOW, 0);
+    }
+
+    // ============================================================================
+    // Vblank and NMI Tests
+    // ============================================================================
+
+    const STATUS_VBLANK: u8 = 0b1000_0000;
+
+    #[test]
+    fn test_vblank_raises_nmi_when_enabled() {
+        let mut ppu = PPU::new(test_mapper());
+        ppu.read_status(); // Clear the power-on vblank flag
+        ppu.write_to_control_reg(0b1000_0000);
+
+        ppu.tick(241 * 341 + 1); // Up to dot 1 of scanline 241
+        assert!(!ppu.poll_nmi());
+        assert_eq!(ppu.registers_snapshot().status & STATUS_VBLANK, 0);
+
+        ppu.tick(1);
+        assert_ne!(ppu.registers_snapshot().status & STATUS_VBLANK, 0);
+        assert!(ppu.poll_nmi());
+        assert!(!ppu.poll_nmi()); // Taken by the first poll
+
+        ppu.tick(20 * 341); // Through dot 1 of the pre-render line
+        assert_eq!(ppu.registers_snapshot().status & STATUS_VBLANK, 0);
+    }
+
+    #[test]
+    fn test_vblank_without_nmi_enabled() {
+        let mut ppu = PPU::new(test_mapper());
+        ppu.tick(242 * 341);
+
+        assert_ne!(ppu.registers_snapshot().status & STATUS_VBLANK, 0);
+        assert!(!ppu.poll_nmi());
+    }
+
+    #[test]
+    fn test_enabling_nmi_during_vblank_raises_nmi() {
+        let mut ppu = PPU::new(test_mapper()); // Vblank is set at power-on
+        ppu.write_to_control_reg(0b1000_0000);
+        assert!(ppu.poll_nmi());
+
+        ppu.write_to_control_reg(0b1000_0000); // Already enabled, no new edge
+        assert!(!ppu.poll_nmi());
+    }
+
+    // ============================================================================
+    // Array Bounds Tests
+    // ============================================================================
+
+    #[test]
+    fn test_sizes_match_constants() {
+        let ppu = PPU::new(test_mapper());
+        assert_eq!(ppu.oam.len(), OAM_SIZE);
+        assert_eq!(ppu.oam.len() / 4, SPRITE_COUNT);
+        assert_eq!(SPRITE_COUNT, 64);
+
+        let frame = Frame::new();
+        assert_eq!((Frame::WIDTH, Frame::HEIGHT), (SCREEN_WIDTH, SCREEN_HEIGHT));
+        assert_eq!(frame.data.len(), SCREEN_WIDTH * SCREEN_HEIGHT * 3);
+    }
+
+    #[test]
+    fn test_four_screen_nametables_are_distinct() {
+        // NROM doesn't map the cartridge VRAM for nametables 2 and 3, the PPU stands in
+        let rom = Rom {
+            prg_rom: vec![0; 16384],
+            chr_rom: vec![0; 8192],
+            mapper: 0,
+            screen_mirroring: Mirroring::FourScreen,
+        };
+        let mut ppu = PPU::new(mapper::new_mapper(&rom).unwrap());
+        for (i, addr) in [0x2000, 0x2400, 0x2800, 0x2c00].into_iter().enumerate() {
+            set_ppu_addr(&mut ppu, addr);
+            ppu.write_to_data_reg(0x10 + i as u8);
+        }
+
+        for (i, addr) in [0x2000, 0x2400, 0x2800, 0x2c00].into_iter().enumerate() {
+            set_ppu_addr(&mut ppu, addr);
+            ppu.read_data(); // Fills the read buffer
+            assert_eq!(ppu.read_data(), 0x10 + i as u8, "{:04x}", addr);
+        }
+        assert_eq!(ppu.vram[0x000], 0x10);
+        assert_eq!(ppu.vram[0x400], 0x11);
+    }
+}
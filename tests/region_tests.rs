@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod test {
+    use nerust_jg::region::Region;
+
+    // ============================================================================
+    // Cycle Conversion Tests
+    // ============================================================================
+
+    #[test]
+    fn test_ntsc_cycle_conversion() {
+        assert_eq!(Region::Ntsc.cpu_to_ppu_cycles(100), 300);
+        assert_eq!(Region::Ntsc.ppu_to_cpu_cycles(300), 100);
+        assert_eq!(Region::Ntsc.ppu_to_cpu_cycles(302), 100);
+    }
+
+    #[test]
+    fn test_pal_cycle_conversion_accumulates() {
+        assert_eq!(Region::Pal.cpu_to_ppu_cycles(5), 16);
+        assert_eq!(Region::Pal.cpu_to_ppu_cycles(1), 3);
+        assert_eq!(Region::Pal.cpu_to_ppu_cycles(100), 320);
+        assert_eq!(Region::Pal.ppu_to_cpu_cycles(320), 100);
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod test {
+    use nerust_jg::render::{decode_tile, decode_tile_row};
+
+    // ============================================================================
+    // Tile Decoding Tests
+    // ============================================================================
+
+    #[test]
+    fn test_decode_tile_row_combines_planes() {
+        // Low plane sets bit 0, high plane bit 1, the MSB is the leftmost pixel
+        assert_eq!(
+            decode_tile_row(0b1100_1010, 0b1010_0110),
+            [3, 1, 2, 0, 1, 2, 3, 0]
+        );
+        assert_eq!(decode_tile_row(0x00, 0x00), [0; 8]);
+        assert_eq!(decode_tile_row(0xFF, 0xFF), [3; 8]);
+    }
+
+    #[test]
+    fn test_decode_tile_reads_high_plane_eight_bytes_later() {
+        let mut chr = [0; 16];
+        chr[0] = 0b1000_0000; // Row 0, low plane
+        chr[15] = 0b0000_0001; // Row 7, high plane
+
+        let tile = decode_tile(&chr);
+        assert_eq!(tile[0], [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(tile[7], [0, 0, 0, 0, 0, 0, 0, 2]);
+        assert!(tile[1..7].iter().all(|row| *row == [0; 8]));
+    }
+}